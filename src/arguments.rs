@@ -0,0 +1,78 @@
+use anyhow::Result;
+use structopt::StructOpt;
+
+/// Extension specific arguments.
+#[derive(Debug, Clone, Default, StructOpt)]
+pub struct Arguments {
+    /// Exclude development dependencies: dependency groups and development sections. These are
+    /// the pyproject.toml and pylock.toml dependency groups, poetry.lock packages outside the main
+    /// group, pdm.lock packages only in development groups, uv.lock packages only required as
+    /// development dependencies and the Pipfile `dev-packages` and Pipfile.lock `develop`
+    /// sections. Optional dependencies (extras) are production dependencies and are kept.
+    #[structopt(long = "production-only")]
+    pub production_only: bool,
+
+    /// Report an error, rather than a warning, if Pipfile.lock is out of date with its Pipfile.
+    #[structopt(long = "error-on-stale-lock")]
+    pub error_on_stale_lock: bool,
+
+    /// Do not print warnings (e.g. stale lock files or dependency cycles) to stderr.
+    #[structopt(long = "quiet")]
+    pub quiet: bool,
+
+    /// Search the working directory tree recursively for dependency definition files. Files which
+    /// fail to parse are reported as a warning and as a dependency, without a registry host, with
+    /// a version error.
+    #[structopt(long = "recursive")]
+    pub recursive: bool,
+
+    /// Write the file, line, column and key path at which each dependency is declared to a JSON
+    /// file.
+    #[structopt(long = "locations-file", parse(from_os_str))]
+    pub locations_file: Option<std::path::PathBuf>,
+
+    /// Resolve the complete pinned dependency tree of a package rather than its direct
+    /// dependencies.
+    #[structopt(long = "transitive")]
+    pub transitive: bool,
+
+    /// Target Python version. Dependencies with environment markers which do not hold for the
+    /// target environment are excluded (default: 3.12).
+    #[structopt(long = "python-version")]
+    pub python_version: Option<String>,
+
+    /// Target platform, as given by Python's `sys.platform` (default: linux).
+    #[structopt(long = "platform")]
+    pub platform: Option<String>,
+
+    /// Target machine type, as given by Python's `platform.machine()` (default: x86_64).
+    #[structopt(long = "platform-machine")]
+    pub platform_machine: Option<String>,
+
+    /// Target Python implementation name, as given by Python's `sys.implementation.name`
+    /// (default: cpython).
+    #[structopt(long = "implementation-name")]
+    pub implementation_name: Option<String>,
+
+    /// Override a target environment marker variable (e.g. `platform_release=6.1.0`). May be
+    /// given multiple times.
+    #[structopt(long = "marker-variable", number_of_values = 1)]
+    pub marker_variables: Vec<String>,
+
+    /// Package extra to include the dependencies of (e.g. `socks`). May be given multiple
+    /// times.
+    #[structopt(long = "extra", number_of_values = 1)]
+    pub extras: Vec<String>,
+
+    /// Write the resolved dependency tree to a JSON file.
+    #[structopt(long = "tree-file", parse(from_os_str))]
+    pub tree_file: Option<std::path::PathBuf>,
+}
+
+impl Arguments {
+    /// Parse extension arguments given by the vouch command line.
+    pub fn parse(extension_args: &Vec<String>) -> Result<Self> {
+        let args = std::iter::once("vouch-py".to_string()).chain(extension_args.iter().cloned());
+        Ok(Self::from_iter_safe(args)?)
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{environment, location, requirements, source};
+
+static DEFAULT_CHANNEL: &str = "defaults";
+
+/// Returns registry host name for conda channel.
+///
+/// Channels may be given by name (e.g. `conda-forge`) or by URL.
+fn get_channel_host_name(channel: &str) -> Result<String> {
+    if channel.contains("://") {
+        return source::get_host_name(&channel);
+    }
+    let channel_name = channel.split('/').next().unwrap_or(channel);
+    Ok(match channel_name {
+        "defaults" | "main" | "r" | "msys2" => "repo.anaconda.com".to_string(),
+        _ => "conda.anaconda.org".to_string(),
+    })
+}
+
+/// Parse conda package version specification.
+///
+/// Exact versions are given as `==version` or `=version=build`. A bare `=version` is a fuzzy match
+/// (e.g. `=1.21` matches `1.21.*`) and is reported with a version error. Returns a structure which
+/// details common errors.
+fn get_parsed_version(version: &str) -> vouch_lib::extension::common::VersionParseResult {
+    let version = version.trim();
+    if version.is_empty() {
+        return Err(vouch_lib::extension::common::VersionError::from_missing_version());
+    }
+
+    let (cleaned_version, is_exact) = match version.strip_prefix("==") {
+        Some(v) => (v, true),
+        None => match version.strip_prefix('=') {
+            // Remove build string.
+            Some(v) => match v.split_once('=') {
+                Some((v, build)) => (v, !build.is_empty()),
+                None => (v, false),
+            },
+            None => (version, false),
+        },
+    };
+    let is_exact = is_exact
+        && !cleaned_version.is_empty()
+        && !cleaned_version.contains(|c| "<>!~*|,=[".contains(c));
+    if is_exact {
+        Ok(cleaned_version.to_string())
+    } else {
+        Err(vouch_lib::extension::common::VersionError::from_parse_error(version))
+    }
+}
+
+/// Parse conda package match specification into channel, name and version.
+///
+/// Example: `conda-forge::numpy=1.21.2=py39h20f2e39_0`
+fn parse_match_spec(
+    spec: &str,
+) -> Result<(
+    Option<String>,
+    String,
+    vouch_lib::extension::common::VersionParseResult,
+)> {
+    let spec = spec.trim();
+    let (channel, spec) = match spec.rfind("::") {
+        Some(index) => (Some(spec[..index].to_string()), &spec[index + 2..]),
+        None => (None, spec),
+    };
+
+    // Space separated form: `name version build`. The version is exact only if a build is given.
+    let mut parts = spec.split_whitespace();
+    let first_part = parts.next().unwrap_or("");
+    let name_length = first_part
+        .find(|c: char| "=<>!~[".contains(c))
+        .unwrap_or(first_part.len());
+    let name = &first_part[..name_length];
+    if name.is_empty() {
+        return Err(format_err!("Failed to parse conda package name: {}", spec));
+    }
+    let version = if name_length < first_part.len() {
+        get_parsed_version(&first_part[name_length..])
+    } else {
+        match (parts.next(), parts.next()) {
+            (Some(v), Some(build)) => get_parsed_version(&format!("={}={}", v, build)),
+            (Some(v), None) => get_parsed_version(&v),
+            (None, _) => get_parsed_version(""),
+        }
+    };
+    Ok((channel, name.to_string(), version))
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Returns one file defined dependencies structure per registry host. Conda packages are
+/// attributed to their channel host. Packages within the nested `pip` section are attributed to
+/// PyPI, or to their source host if given by URL or path. Requirements files included from the
+/// `pip` section are relative to the environment file.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    target_environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let environment: serde_yaml::Value = serde_yaml::from_str(&content).context(format!(
+        "Failed to parse conda environment file: {}",
+        file_path.display()
+    ))?;
+
+    let default_channel = environment["channels"]
+        .as_sequence()
+        .and_then(|channels| {
+            channels
+                .iter()
+                .filter_map(|channel| channel.as_str())
+                .find(|channel| *channel != "nodefaults")
+        })
+        .unwrap_or(DEFAULT_CHANNEL)
+        .to_string();
+    let entries = match environment["dependencies"].as_sequence() {
+        Some(v) => v.clone(),
+        None => Vec::new(),
+    };
+
+    let mut conda_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    let mut pip_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if let Some(spec) = entry.as_str() {
+            let (channel, name, version) = parse_match_spec(&spec).context(format!(
+                "Failed to parse dependencies entry '{}' of conda environment file: {}",
+                spec,
+                file_path.display()
+            ))?;
+            let host_name = get_channel_host_name(&channel.unwrap_or(default_channel.clone()))?;
+            conda_dependencies
+                .entry(host_name)
+                .or_insert_with(location::Dependencies::new)
+                .insert(
+                    vouch_lib::extension::Dependency { name, version },
+                    location::Location::find(&file_path, &content, &["dependencies", spec])
+                        .with_key_path(&format!("dependencies[{}]", index)),
+                );
+            continue;
+        }
+
+        let pip_entries = entry["pip"].as_sequence().ok_or(format_err!(
+            "Failed to parse dependencies entry of conda environment file: {}",
+            file_path.display()
+        ))?;
+        for (pip_index, pip_entry) in pip_entries.iter().enumerate() {
+            let line = pip_entry.as_str().ok_or(format_err!(
+                "Failed to parse pip entry of conda environment file: {}",
+                file_path.display()
+            ))?;
+            if let Some((include_path, _)) = requirements::parse_include_option(&line) {
+                let include_file_path = file_path
+                    .parent()
+                    .map(|parent| parent.join(&include_path))
+                    .unwrap_or(std::path::PathBuf::from(&include_path));
+                if include_file_path.is_file() {
+                    requirements_file_paths.push(include_file_path);
+                } else {
+                    pip_dependencies
+                        .entry(source::PYPI_HOST_NAME.to_string())
+                        .or_insert_with(location::Dependencies::new)
+                        .insert(
+                            requirements::to_missing_file_dependency(&include_path),
+                            location::Location::find(
+                                &file_path,
+                                &content,
+                                &["dependencies", "pip", line],
+                            )
+                            .with_key_path(&format!("dependencies[{}].pip[{}]", index, pip_index)),
+                        );
+                }
+                continue;
+            }
+            let requirement = requirements::parse_requirement_line(&line).context(format!(
+                "Failed to parse pip entry '{}' of conda environment file: {}",
+                line,
+                file_path.display()
+            ))?;
+            let requirement = match requirement {
+                Some(v) => v,
+                None => continue,
+            };
+            if requirements::applies(&requirement, &target_environment)? {
+                pip_dependencies
+                    .entry(requirements::get_host_name(
+                        &requirement,
+                        source::PYPI_HOST_NAME,
+                    ))
+                    .or_insert_with(location::Dependencies::new)
+                    .insert(
+                        requirements::to_dependency(&requirement),
+                        location::Location::find(
+                            &file_path,
+                            &content,
+                            &["dependencies", "pip", line],
+                        )
+                        .with_key_path(&format!("dependencies[{}].pip[{}]", index, pip_index)),
+                    );
+            }
+        }
+    }
+
+    let mut all_file_defined_dependencies =
+        location::from_registry_hosts(&file_path, conda_dependencies);
+    all_file_defined_dependencies
+        .extend(location::from_registry_hosts(&file_path, pip_dependencies));
+    Ok(all_file_defined_dependencies)
+}
+
+/// Returns conda environment files found within the given directory.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    vec!["environment.yml", "environment.yaml"]
+        .into_iter()
+        .map(|file_name| directory.join(file_name))
+        .filter(|path| path.is_file())
+        .collect()
+}
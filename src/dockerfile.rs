@@ -0,0 +1,293 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{environment, location, pip, requirements, source};
+
+/// Dockerfile instruction, its arguments and the line on which it starts.
+#[derive(Debug, Clone)]
+struct Instruction {
+    keyword: String,
+    arguments: String,
+    line: usize,
+}
+
+/// Parse Dockerfile content into instructions.
+///
+/// Joins lines ending with a backslash and removes comment lines.
+fn parse_instructions(content: &str) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut current_line = String::new();
+    let mut current_line_number: Option<usize> = None;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed_line = line.trim();
+        if trimmed_line.starts_with('#') {
+            continue;
+        }
+        if current_line_number.is_none() {
+            current_line_number = Some(index + 1);
+        }
+        match trimmed_line.strip_suffix('\\') {
+            Some(v) => {
+                current_line.push_str(v);
+                current_line.push(' ');
+                continue;
+            }
+            None => current_line.push_str(trimmed_line),
+        }
+
+        let line = std::mem::take(&mut current_line);
+        let line = line.trim();
+        let line_number = current_line_number.take().unwrap_or(index + 1);
+        if line.is_empty() {
+            continue;
+        }
+        let (keyword, arguments) = match line.find(char::is_whitespace) {
+            Some(index) => (&line[..index], line[index..].trim()),
+            None => (line, ""),
+        };
+        instructions.push(Instruction {
+            keyword: keyword.to_uppercase(),
+            arguments: arguments.to_string(),
+            line: line_number,
+        });
+    }
+    instructions
+}
+
+/// Split instruction arguments into flags (e.g. `--from=builder`) and remaining arguments.
+///
+/// Arguments given in exec form (JSON array) are returned as separate values.
+fn split_arguments(arguments: &str) -> (Vec<String>, Vec<String>) {
+    let mut flags = Vec::new();
+    let mut remainder = arguments.trim();
+    while remainder.starts_with("--") {
+        let end = remainder
+            .find(char::is_whitespace)
+            .unwrap_or(remainder.len());
+        flags.push(remainder[..end].to_string());
+        remainder = remainder[end..].trim_start();
+    }
+
+    if remainder.starts_with('[') {
+        if let Ok(values) = serde_json::from_str::<Vec<String>>(&remainder) {
+            return (flags, values);
+        }
+    }
+    (flags, vec![remainder.to_string()])
+}
+
+/// Mapping from a path within the image to a path within the build context.
+#[derive(Debug, Clone)]
+struct CopyMapping {
+    image_path: std::path::PathBuf,
+    context_path: std::path::PathBuf,
+}
+
+/// Returns image path joined to the working directory if relative.
+fn get_image_path(working_directory: &std::path::PathBuf, path: &str) -> std::path::PathBuf {
+    working_directory.join(path)
+}
+
+/// Returns copy mappings for `COPY` or `ADD` instruction arguments.
+fn get_copy_mappings(
+    arguments: &str,
+    working_directory: &std::path::PathBuf,
+    context_directory: &std::path::Path,
+) -> Vec<CopyMapping> {
+    let (flags, values) = split_arguments(&arguments);
+    // Files copied from other build stages or images are not within the build context.
+    if flags.iter().any(|flag| flag.starts_with("--from")) {
+        return Vec::new();
+    }
+    let values = if values.len() == 1 {
+        values[0]
+            .split_whitespace()
+            .map(|v| v.to_string())
+            .collect()
+    } else {
+        values
+    };
+    let (destination, sources) = match values.split_last() {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    let destination_path = get_image_path(&working_directory, &destination);
+    let is_destination_directory = destination.ends_with('/') || sources.len() > 1;
+    sources
+        .iter()
+        .map(|source| {
+            let context_path = context_directory.join(source);
+            // Directory sources copy their contents into the destination.
+            let image_path = if is_destination_directory && !context_path.is_dir() {
+                let file_name = context_path
+                    .file_name()
+                    .map(|name| name.to_os_string())
+                    .unwrap_or_default();
+                destination_path.join(file_name)
+            } else {
+                destination_path.clone()
+            };
+            CopyMapping {
+                image_path,
+                context_path,
+            }
+        })
+        .collect()
+}
+
+/// Returns the build context path of a file copied into the image.
+fn resolve_image_path(
+    image_path: &std::path::PathBuf,
+    copy_mappings: &Vec<CopyMapping>,
+) -> Option<std::path::PathBuf> {
+    // Later copies override earlier copies.
+    for mapping in copy_mappings.iter().rev() {
+        let context_path = match image_path.strip_prefix(&mapping.image_path) {
+            Ok(v) if v.as_os_str().is_empty() => mapping.context_path.clone(),
+            // File sources copied onto an existing image directory keep their file name.
+            Ok(v) if mapping.context_path.is_file() => {
+                if Some(v.as_os_str()) != mapping.context_path.file_name() {
+                    continue;
+                }
+                mapping.context_path.clone()
+            }
+            Ok(v) => mapping.context_path.join(v),
+            Err(_) => continue,
+        };
+        if context_path.is_file() {
+            return Some(context_path);
+        }
+    }
+    None
+}
+
+/// Parse dependencies from `pip install` invocations within Dockerfile `RUN` instructions.
+///
+/// Returns one file defined dependencies structure per registry host for packages installed
+/// directly. Requirements files installed with `-r` are mapped back to the build context, which
+/// is assumed to be the Dockerfile's directory, using preceding `COPY` and `ADD` instructions.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let context_directory = file_path
+        .parent()
+        .unwrap_or(std::path::Path::new("/"))
+        .to_path_buf();
+
+    let mut working_directory = std::path::PathBuf::from("/");
+    let mut copy_mappings = Vec::new();
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for instruction in parse_instructions(&content) {
+        match instruction.keyword.as_str() {
+            // Each build stage starts with a new file system.
+            "FROM" => {
+                working_directory = std::path::PathBuf::from("/");
+                copy_mappings.clear();
+            }
+            "WORKDIR" => {
+                working_directory = get_image_path(&working_directory, &instruction.arguments);
+            }
+            "COPY" | "ADD" => {
+                copy_mappings.extend(get_copy_mappings(
+                    &instruction.arguments,
+                    &working_directory,
+                    &context_directory,
+                ));
+            }
+            "RUN" => {
+                let (_, values) = split_arguments(&instruction.arguments);
+                // Exec form commands such as `["sh", "-c", "..."]` are handled as shell commands.
+                let command_line = if values.len() > 1 && values[0].ends_with("sh") {
+                    values.last().cloned().unwrap_or_default()
+                } else {
+                    values.join(" ")
+                };
+
+                for install_command in pip::parse_install_commands(&command_line) {
+                    let host_name = match &install_command.index_url {
+                        Some(url) => source::get_index_host_name(&url)?,
+                        None => source::PYPI_HOST_NAME.to_string(),
+                    };
+                    for requirement_string in &install_command.requirement_strings {
+                        let dependency =
+                            pip::get_dependency(&requirement_string, &host_name, &environment)
+                                .context(format!(
+                                    "Failed to parse pip install argument '{}' in file: {}",
+                                    requirement_string,
+                                    file_path.display()
+                                ))?;
+                        if let Some((dependency_host_name, dependency)) = dependency {
+                            let location = location::Location::find_from_line(
+                                &file_path,
+                                &content,
+                                instruction.line,
+                                &[requirement_string],
+                            );
+                            all_dependencies
+                                .entry(dependency_host_name)
+                                .or_insert_with(location::Dependencies::new)
+                                .insert(dependency, location);
+                        }
+                    }
+
+                    for requirements_file in &install_command.requirements_files {
+                        let image_path = get_image_path(&working_directory, &requirements_file);
+                        match resolve_image_path(&image_path, &copy_mappings) {
+                            Some(v) => requirements_file_paths.push(v),
+                            None => {
+                                all_dependencies
+                                    .entry(host_name.clone())
+                                    .or_insert_with(location::Dependencies::new)
+                                    .insert(
+                                        requirements::to_missing_file_dependency(
+                                            &requirements_file,
+                                        ),
+                                        location::Location::find_from_line(
+                                            &file_path,
+                                            &content,
+                                            instruction.line,
+                                            &[requirements_file],
+                                        ),
+                                    );
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+/// Returns Dockerfiles found within the given directory.
+///
+/// Includes `Dockerfile`, `Containerfile`, `Dockerfile.<name>` and `<name>.Dockerfile`.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_dockerfile_name =
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| {
+                        name == "Dockerfile"
+                            || name == "Containerfile"
+                            || name.starts_with("Dockerfile.")
+                            || name.ends_with(".Dockerfile")
+                            || name.ends_with(".dockerfile")
+                    });
+            if path.is_file() && is_dockerfile_name {
+                file_paths.push(path);
+            }
+        }
+    }
+    file_paths.sort();
+    file_paths
+}
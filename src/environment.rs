@@ -0,0 +1,164 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{arguments, marker, pep440};
+
+static DEFAULT_PYTHON_VERSION: &str = "3.12";
+static DEFAULT_PLATFORM: &str = "linux";
+static DEFAULT_PLATFORM_MACHINE: &str = "x86_64";
+static DEFAULT_IMPLEMENTATION_NAME: &str = "cpython";
+
+/// Target environment for which dependencies are identified and resolved.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub python_version: pep440::Version,
+    // Value of Python's `sys.platform` (e.g. `linux`, `win32` or `darwin`).
+    pub platform: String,
+    // Values of PEP 508 environment marker variables (e.g. `platform_machine`).
+    pub marker_variables: BTreeMap<String, String>,
+}
+
+impl Environment {
+    /// Create a target environment. Marker variables which are not given are derived from the
+    /// platform and implementation (e.g. `os_name` is `nt` for the `win32` platform).
+    pub fn new(
+        python_version: &str,
+        platform: &str,
+        platform_machine: &str,
+        implementation_name: &str,
+    ) -> Result<Self> {
+        let python_version = pep440::Version::parse(&python_version)
+            .context("Failed to parse target Python version.")?;
+        let python_full_version = format!(
+            "{}.{}.{}",
+            python_version.release_component(0),
+            python_version.release_component(1),
+            python_version.release_component(2)
+        );
+        let os_name = match platform {
+            "win32" => "nt",
+            _ => "posix",
+        };
+        let platform_system = match platform {
+            "linux" => "Linux",
+            "win32" => "Windows",
+            "darwin" => "Darwin",
+            "cygwin" => "CYGWIN_NT",
+            v if v.starts_with("freebsd") => "FreeBSD",
+            _ => "",
+        };
+        let platform_python_implementation = match implementation_name {
+            "cpython" => "CPython",
+            "pypy" => "PyPy",
+            "ironpython" => "IronPython",
+            "jython" => "Jython",
+            v => v,
+        };
+
+        let mut marker_variables = BTreeMap::new();
+        for (name, value) in vec![
+            (
+                "python_version",
+                format!(
+                    "{}.{}",
+                    python_version.release_component(0),
+                    python_version.release_component(1)
+                ),
+            ),
+            ("python_full_version", python_full_version.clone()),
+            ("os_name", os_name.to_string()),
+            ("sys_platform", platform.to_string()),
+            ("platform_release", String::new()),
+            ("platform_system", platform_system.to_string()),
+            ("platform_version", String::new()),
+            ("platform_machine", platform_machine.to_string()),
+            (
+                "platform_python_implementation",
+                platform_python_implementation.to_string(),
+            ),
+            ("implementation_name", implementation_name.to_string()),
+            ("implementation_version", python_full_version),
+        ] {
+            marker_variables.insert(name.to_string(), value);
+        }
+
+        Ok(Self {
+            python_version: python_version,
+            platform: platform.to_string(),
+            marker_variables: marker_variables,
+        })
+    }
+
+    /// Create the target environment given by the extension arguments.
+    ///
+    /// Marker variables given as `NAME=VALUE` pairs override derived values.
+    pub fn from_arguments(arguments: &arguments::Arguments) -> Result<Self> {
+        let mut environment = Self::new(
+            arguments
+                .python_version
+                .as_deref()
+                .unwrap_or(DEFAULT_PYTHON_VERSION),
+            arguments.platform.as_deref().unwrap_or(DEFAULT_PLATFORM),
+            arguments
+                .platform_machine
+                .as_deref()
+                .unwrap_or(DEFAULT_PLATFORM_MACHINE),
+            arguments
+                .implementation_name
+                .as_deref()
+                .unwrap_or(DEFAULT_IMPLEMENTATION_NAME),
+        )?;
+        for marker_variable in &arguments.marker_variables {
+            let (name, value) = marker_variable.split_once('=').ok_or(format_err!(
+                "Failed to parse marker variable, expected NAME=VALUE: {}",
+                marker_variable
+            ))?;
+            let name = name.trim();
+            if !marker::VARIABLES.contains(&name) || name == "extra" {
+                return Err(format_err!("Unknown marker variable: {}", name));
+            }
+            environment
+                .marker_variables
+                .insert(name.to_string(), value.trim().to_string());
+        }
+        Ok(environment)
+    }
+
+    /// Returns true if a wheel with the given platform tag can be installed.
+    ///
+    /// Example tags: `any`, `manylinux_2_17_x86_64`, `win_amd64`, `macosx_11_0_arm64`.
+    pub fn is_compatible_platform_tag(&self, tag: &str) -> bool {
+        let prefixes: &[&str] = match self.platform.as_str() {
+            "linux" => &["manylinux", "musllinux", "linux"],
+            "win32" | "cygwin" => &["win"],
+            "darwin" => &["macosx"],
+            _ => &[],
+        };
+        tag == "any" || prefixes.iter().any(|prefix| tag.starts_with(prefix))
+    }
+
+    /// Returns true if a wheel with the given Python and ABI tags can be installed.
+    ///
+    /// Example tags: `py3` and `none`, `cp311` and `cp311`, `cp38` and `abi3`.
+    pub fn is_compatible_python_tag(&self, python_tag: &str, abi_tag: &str) -> bool {
+        let major = self.python_version.release_component(0);
+        let minor = self.python_version.release_component(1);
+        let implementation_length = python_tag
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(python_tag.len());
+        let (implementation, version) = python_tag.split_at(implementation_length);
+        if implementation != "py" && implementation != "cp" {
+            return false;
+        }
+        let tag_major = version.get(..1).and_then(|v| v.parse::<u64>().ok());
+        let tag_minor = version.get(1..).and_then(|v| v.parse::<u64>().ok());
+        match (implementation, tag_major, tag_minor) {
+            (_, Some(tag_major), _) if tag_major != major => false,
+            ("py", _, tag_minor) => tag_minor.map_or(true, |tag_minor| tag_minor <= minor),
+            // The stable ABI is forward compatible.
+            ("cp", _, Some(tag_minor)) if abi_tag == "abi3" => tag_minor <= minor,
+            ("cp", _, Some(tag_minor)) => tag_minor == minor,
+            _ => false,
+        }
+    }
+}
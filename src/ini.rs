@@ -0,0 +1,66 @@
+/// INI file section and its key value entries.
+///
+/// Entry values keep their continuation lines, separated by newlines.
+#[derive(Debug, Clone, Default)]
+pub struct Section {
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl Section {
+    /// Returns value of the first entry with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse INI file content using Python configparser semantics.
+///
+/// Lines starting with '#' or ';' are comments. Indented lines continue the previous value.
+pub fn parse(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    for line in content.lines() {
+        let trimmed_line = line.trim();
+        if trimmed_line.starts_with('#') || trimmed_line.starts_with(';') {
+            continue;
+        }
+
+        let is_continuation = line.starts_with(|c: char| c.is_whitespace());
+        if is_continuation {
+            if let Some((_, value)) = sections
+                .last_mut()
+                .and_then(|section| section.entries.last_mut())
+            {
+                value.push('\n');
+                value.push_str(trimmed_line);
+            }
+            continue;
+        }
+        if trimmed_line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = trimmed_line
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+        {
+            sections.push(Section {
+                name: name.trim().to_string(),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let separator_index = trimmed_line.find(|c| c == '=' || c == ':');
+        if let (Some(index), Some(section)) = (separator_index, sections.last_mut()) {
+            section.entries.push((
+                trimmed_line[..index].trim().to_string(),
+                trimmed_line[index + 1..].trim().to_string(),
+            ));
+        }
+    }
+    sections
+}
@@ -2,7 +2,35 @@ use anyhow::{format_err, Context, Result};
 use std::io::Read;
 use strum::IntoEnumIterator;
 
+mod arguments;
+mod conda;
+mod dockerfile;
+mod environment;
+mod ini;
+mod location;
+mod marker;
+mod notebook;
+mod nox;
+mod package;
+mod pdm;
+mod pep440;
+mod pip;
 mod pipfile;
+mod poetry;
+mod pylock;
+mod pyproject;
+mod requirements;
+mod resolver;
+mod script;
+mod setup_cfg;
+mod setup_py;
+mod site_packages;
+mod source;
+mod tokenizer;
+mod tox;
+mod uv;
+mod version_error;
+mod warning;
 
 #[derive(Clone, Debug)]
 pub struct PyExtension {
@@ -40,42 +68,95 @@ impl vouch_lib::extension::Extension for PyExtension {
     /// Returns one package dependencies structure per registry.
     fn identify_package_dependencies(
         &self,
-        _package_name: &str,
-        _package_version: &Option<&str>,
-        _extension_args: &Vec<String>,
+        package_name: &str,
+        package_version: &Option<&str>,
+        extension_args: &Vec<String>,
     ) -> Result<Vec<vouch_lib::extension::PackageDependencies>> {
-        Err(format_err!("Function unimplemented."))
+        let arguments = arguments::Arguments::parse(&extension_args)?;
+        let package_dependencies = if arguments.transitive {
+            package::get_transitive_package_dependencies(
+                &package_name,
+                &package_version,
+                &arguments,
+            )?
+        } else {
+            package::get_package_dependencies(&package_name, &package_version, &arguments)?
+        };
+        Ok(vec![package_dependencies])
     }
 
     fn identify_file_defined_dependencies(
         &self,
         working_directory: &std::path::PathBuf,
-        _extension_args: &Vec<String>,
+        extension_args: &Vec<String>,
     ) -> Result<Vec<vouch_lib::extension::FileDefinedDependencies>> {
+        let arguments = arguments::Arguments::parse(&extension_args)?;
+        let environment = environment::Environment::from_arguments(&arguments)?;
+
         // Identify all dependency definition files.
-        let dependency_files = match identify_dependency_files(&working_directory) {
-            Some(v) => v,
-            None => return Ok(Vec::new()),
+        let dependency_files = if arguments.recursive {
+            identify_dependency_files_recursive(&working_directory)?
+        } else {
+            match identify_dependency_files(&working_directory) {
+                Some(v) => v,
+                None => return Ok(Vec::new()),
+            }
         };
 
         // Read all dependencies definitions files.
         let mut all_dependency_specs = Vec::new();
+        let mut requirements_file_paths = Vec::new();
         for dependency_file in dependency_files {
-            // TODO: Add support for parsing all definition file types.
-            let (dependencies, registry_host_name) = match dependency_file.r#type {
-                DependencyFileType::PipfileLock => (
-                    pipfile::get_dependencies(&dependency_file.path)?,
-                    pipfile::get_registry_host_name(),
-                ),
-            };
-            all_dependency_specs.push(vouch_lib::extension::FileDefinedDependencies {
-                path: dependency_file.path,
-                registry_host_name: registry_host_name,
-                dependencies: dependencies.into_iter().collect(),
-            });
-        }
-
-        Ok(all_dependency_specs)
+            let mut file_requirements_file_paths = Vec::new();
+            let dependency_specs = parse_dependency_file(
+                &dependency_file,
+                &arguments,
+                &environment,
+                &mut file_requirements_file_paths,
+            )
+            .context(format!(
+                "Failed to parse dependency file: {}",
+                dependency_file.path.display()
+            ));
+            match dependency_specs {
+                Ok(v) => {
+                    all_dependency_specs.extend(v);
+                    requirements_file_paths.extend(file_requirements_file_paths);
+                }
+                // A malformed file should not discard the dependencies of a whole monorepo.
+                Err(error) if arguments.recursive => {
+                    all_dependency_specs.push(get_parse_error_dependencies(
+                        &dependency_file.path,
+                        &error,
+                        &arguments,
+                    ));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        if !requirements_file_paths.is_empty() {
+            let (dependency_specs, file_errors) =
+                requirements::get_file_defined_dependencies(&requirements_file_paths, &environment);
+            for (file_path, error) in file_errors {
+                let error = error.context(format!(
+                    "Failed to parse dependency file: {}",
+                    file_path.display()
+                ));
+                if !arguments.recursive {
+                    return Err(error);
+                }
+                all_dependency_specs
+                    .push(get_parse_error_dependencies(&file_path, &error, &arguments));
+            }
+            all_dependency_specs.extend(dependency_specs);
+        }
+
+        if let Some(locations_file_path) = &arguments.locations_file {
+            location::write_locations_file(&locations_file_path, &all_dependency_specs)?;
+        }
+        Ok(location::into_file_defined_dependencies(
+            all_dependency_specs,
+        ))
     }
 
     fn registries_package_metadata(
@@ -189,17 +270,68 @@ fn get_archive_url(
 #[derive(Debug, Copy, Clone, strum_macros::EnumIter)]
 enum DependencyFileType {
     PipfileLock,
+    Pipfile,
+    RequirementsTxt,
+    PoetryLock,
+    PyprojectToml,
+    UvLock,
+    PdmLock,
+    PylockToml,
+    EnvironmentYml,
+    SetupCfg,
+    SetupPy,
+    SitePackages,
+    Dockerfile,
+    Notebook,
+    Tox,
+    Noxfile,
+    InlineScriptMetadata,
 }
 
 impl DependencyFileType {
-    /// Return file name associated with dependency type.
-    pub fn file_name(&self) -> std::path::PathBuf {
+    /// Return paths of files (or directories) associated with dependency type within the given
+    /// directory.
+    pub fn find_files(&self, directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+        match self {
+            Self::PipfileLock => find_file(&directory, "Pipfile.lock"),
+            Self::Pipfile => pipfile::find_files(&directory),
+            Self::RequirementsTxt => requirements::find_files(&directory),
+            Self::PoetryLock => find_file(&directory, "poetry.lock"),
+            Self::PyprojectToml => find_file(&directory, "pyproject.toml"),
+            Self::UvLock => find_file(&directory, "uv.lock"),
+            Self::PdmLock => find_file(&directory, "pdm.lock"),
+            Self::PylockToml => pylock::find_files(&directory),
+            Self::EnvironmentYml => conda::find_files(&directory),
+            Self::SetupCfg => find_file(&directory, "setup.cfg"),
+            Self::SetupPy => find_file(&directory, "setup.py"),
+            Self::SitePackages => site_packages::find_directories(&directory),
+            Self::Dockerfile => dockerfile::find_files(&directory),
+            Self::Notebook => notebook::find_files(&directory),
+            Self::Tox => tox::find_files(&directory),
+            Self::Noxfile => find_file(&directory, "noxfile.py"),
+            Self::InlineScriptMetadata => script::find_files(&directory),
+        }
+    }
+
+    /// Return true if dependency type is only considered when no other types are found.
+    pub fn is_fallback(&self) -> bool {
         match self {
-            Self::PipfileLock => std::path::PathBuf::from("Pipfile.lock"),
+            Self::InlineScriptMetadata => true,
+            _ => false,
         }
     }
 }
 
+/// Returns the file path if the named file exists within the given directory.
+fn find_file(directory: &std::path::PathBuf, file_name: &str) -> Vec<std::path::PathBuf> {
+    let path = directory.join(file_name);
+    if path.is_file() {
+        vec![path]
+    } else {
+        Vec::new()
+    }
+}
+
 /// Package dependency file type and file path.
 #[derive(Debug, Clone)]
 struct DependencyFile {
@@ -207,6 +339,144 @@ struct DependencyFile {
     path: std::path::PathBuf,
 }
 
+/// Parse dependencies from a dependency definition file.
+///
+/// Requirements files are not parsed. Their paths, and the paths of requirements files installed
+/// by the dependency file (e.g. `pip install -r`), are added to the given requirements file paths,
+/// so that all requirements files are parsed together. Installed requirements files which do not
+/// exist are reported as dependencies with a version error.
+fn parse_dependency_file(
+    dependency_file: &DependencyFile,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let file_path = &dependency_file.path;
+    Ok(match dependency_file.r#type {
+        DependencyFileType::PipfileLock => {
+            pipfile::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::Pipfile => {
+            pipfile::get_pipfile_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::PoetryLock => {
+            poetry::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::PyprojectToml => {
+            pyproject::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::UvLock => {
+            uv::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::PdmLock => {
+            pdm::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::PylockToml => {
+            pylock::get_file_defined_dependencies(&file_path, &arguments, &environment)?
+        }
+        DependencyFileType::EnvironmentYml => {
+            conda::get_file_defined_dependencies(&file_path, &environment, requirements_file_paths)?
+        }
+        DependencyFileType::SetupCfg => {
+            setup_cfg::get_file_defined_dependencies(&file_path, &environment)?
+        }
+        DependencyFileType::SetupPy => {
+            setup_py::get_file_defined_dependencies(&file_path, &environment)?
+        }
+        DependencyFileType::SitePackages => {
+            site_packages::get_file_defined_dependencies(&file_path)?
+        }
+        DependencyFileType::InlineScriptMetadata => {
+            script::get_file_defined_dependencies(&file_path, &environment)?
+        }
+        DependencyFileType::Dockerfile => dockerfile::get_file_defined_dependencies(
+            &file_path,
+            &environment,
+            requirements_file_paths,
+        )?,
+        DependencyFileType::Notebook => notebook::get_file_defined_dependencies(
+            &file_path,
+            &environment,
+            requirements_file_paths,
+        )?,
+        DependencyFileType::Tox => {
+            tox::get_file_defined_dependencies(&file_path, &environment, requirements_file_paths)?
+        }
+        DependencyFileType::Noxfile => {
+            nox::get_file_defined_dependencies(&file_path, &environment, requirements_file_paths)?
+        }
+        // Requirements files may include each other and may also be installed by other
+        // files (e.g. Dockerfile `-r` arguments). Parse them together so that each file is
+        // reported once.
+        DependencyFileType::RequirementsTxt => {
+            requirements_file_paths.push(file_path.clone());
+            Vec::new()
+        }
+    })
+}
+
+/// Returns file defined dependencies which report that the dependency file failed to parse.
+///
+/// The failure is printed as a warning and reported as a dependency named by the file, with the
+/// error as its version error. The entry has no registry host, so that it can not be mistaken for
+/// a registry package.
+fn get_parse_error_dependencies(
+    file_path: &std::path::PathBuf,
+    error: &anyhow::Error,
+    arguments: &arguments::Arguments,
+) -> location::FileDefinedDependencies {
+    warning::print(&format!("{:#}", error), &arguments);
+    let mut dependencies = location::Dependencies::new();
+    dependencies.insert(
+        vouch_lib::extension::Dependency {
+            name: file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            version: Err(
+                vouch_lib::extension::common::VersionError::from_parse_error(&format!(
+                    "{:#}",
+                    error
+                )),
+            ),
+        },
+        location::Location {
+            path: file_path.clone(),
+            line: None,
+            column: None,
+            key_path: None,
+        },
+    );
+    location::FileDefinedDependencies {
+        path: file_path.clone(),
+        registry_host_name: String::new(),
+        dependencies: dependencies,
+    }
+}
+
+/// Returns dependency definition files found within the given directory.
+///
+/// Fallback file types are only considered if no other file types are found.
+fn find_dependency_files(directory: &std::path::PathBuf) -> Vec<DependencyFile> {
+    let mut dependency_files: Vec<DependencyFile> = Vec::new();
+    for is_fallback in vec![false, true] {
+        for dependency_file_type in
+            DependencyFileType::iter().filter(|t| t.is_fallback() == is_fallback)
+        {
+            for target_absolute_path in dependency_file_type.find_files(&directory) {
+                dependency_files.push(DependencyFile {
+                    r#type: dependency_file_type,
+                    path: target_absolute_path,
+                })
+            }
+        }
+        if !dependency_files.is_empty() {
+            break;
+        }
+    }
+    dependency_files
+}
+
 /// Returns a vector of identified package dependency definition files.
 ///
 /// Walks up the directory tree directory tree until the first positive result is found.
@@ -218,20 +488,8 @@ fn identify_dependency_files(
 
     loop {
         // If at least one target is found, assume package is present.
-        let mut found_dependency_file = false;
-
-        let mut dependency_files: Vec<DependencyFile> = Vec::new();
-        for dependency_file_type in DependencyFileType::iter() {
-            let target_absolute_path = working_directory.join(dependency_file_type.file_name());
-            if target_absolute_path.is_file() {
-                found_dependency_file = true;
-                dependency_files.push(DependencyFile {
-                    r#type: dependency_file_type,
-                    path: target_absolute_path,
-                })
-            }
-        }
-        if found_dependency_file {
+        let dependency_files = find_dependency_files(&working_directory);
+        if !dependency_files.is_empty() {
             return Some(dependency_files);
         }
 
@@ -245,3 +503,58 @@ fn identify_dependency_files(
     }
     None
 }
+
+/// Directory names which are never searched for dependency definition files.
+static SKIPPED_DIRECTORY_NAMES: &[&str] = &[".git", "node_modules", "__pycache__"];
+
+/// Returns true if the directory should be searched for dependency definition files.
+///
+/// Skips version control metadata, `node_modules` and virtual environments.
+fn is_searched_directory(directory: &std::path::Path) -> bool {
+    let is_skipped_name = directory
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| SKIPPED_DIRECTORY_NAMES.contains(&name));
+    !is_skipped_name && !directory.join("pyvenv.cfg").is_file()
+}
+
+/// Returns package dependency definition files found anywhere within the working directory tree.
+///
+/// Walks down the directory tree. Files and directories excluded by .gitignore files are
+/// skipped.
+fn identify_dependency_files_recursive(
+    working_directory: &std::path::PathBuf,
+) -> Result<Vec<DependencyFile>> {
+    assert!(working_directory.is_absolute());
+
+    let mut directories = Vec::new();
+    let mut searched_paths = std::collections::HashSet::new();
+    let walker = ignore::WalkBuilder::new(&working_directory)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| {
+            !entry.file_type().map_or(false, |t| t.is_dir()) || is_searched_directory(entry.path())
+        })
+        .build();
+    for entry in walker {
+        let entry = entry.context(format!(
+            "Failed to walk directory tree: {}",
+            working_directory.display()
+        ))?;
+        if entry.file_type().map_or(false, |t| t.is_dir()) {
+            directories.push(entry.path().to_path_buf());
+        }
+        searched_paths.insert(entry.into_path());
+    }
+    directories.sort();
+
+    let mut dependency_files = Vec::new();
+    for directory in directories {
+        dependency_files.extend(
+            find_dependency_files(&directory)
+                .into_iter()
+                .filter(|dependency_file| searched_paths.contains(&dependency_file.path)),
+        );
+    }
+    Ok(dependency_files)
+}
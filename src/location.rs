@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+
+/// Location at which a dependency is declared.
+///
+/// Line and column numbers start at 1. Dependencies declared within JSON or TOML files also
+/// record the key path (e.g. `default.requests` or `package[3]`) of their entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Location {
+    pub path: std::path::PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub key_path: Option<String>,
+}
+
+impl Location {
+    pub fn from_line(path: &std::path::PathBuf, line: usize) -> Self {
+        Self {
+            path: path.clone(),
+            line: Some(line),
+            column: None,
+            key_path: None,
+        }
+    }
+
+    /// Create a location by searching the file content for the declaration.
+    ///
+    /// See `find_position` for the meaning of `patterns`.
+    pub fn find(path: &std::path::PathBuf, content: &str, patterns: &[&str]) -> Self {
+        Self::find_from_line(&path, &content, 1, &patterns)
+    }
+
+    /// Create a location by searching the file content from the given line onwards.
+    pub fn find_from_line(
+        path: &std::path::PathBuf,
+        content: &str,
+        line: usize,
+        patterns: &[&str],
+    ) -> Self {
+        let skipped_content_length: usize = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|v| v.len())
+            .sum();
+        let position = find_position(&content[skipped_content_length..], &patterns)
+            .map(|(found_line, column)| (found_line + line.max(1) - 1, column));
+        Self {
+            path: path.clone(),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            key_path: None,
+        }
+    }
+
+    pub fn with_key_path(mut self, key_path: &str) -> Self {
+        self.key_path = Some(key_path.to_string());
+        self
+    }
+}
+
+/// Dependencies and the location at which each is declared.
+pub type Dependencies = HashMap<vouch_lib::extension::Dependency, Location>;
+
+/// File defined dependencies and the location of each dependency.
+#[derive(Debug, Clone)]
+pub struct FileDefinedDependencies {
+    pub path: std::path::PathBuf,
+    pub registry_host_name: String,
+    pub dependencies: Dependencies,
+}
+
+/// Returns one file defined dependencies structure per registry host.
+pub fn from_registry_hosts(
+    path: &std::path::PathBuf,
+    all_dependencies: BTreeMap<String, Dependencies>,
+) -> Vec<FileDefinedDependencies> {
+    all_dependencies
+        .into_iter()
+        .map(
+            |(registry_host_name, dependencies)| FileDefinedDependencies {
+                path: path.clone(),
+                registry_host_name: registry_host_name,
+                dependencies: dependencies,
+            },
+        )
+        .collect()
+}
+
+/// Returns the line and column at which the last pattern is found.
+///
+/// Each pattern is searched for after the previous match, e.g. `["\"default\"", "\"requests\""]`
+/// finds the `requests` key within the `default` object.
+pub fn find_position(content: &str, patterns: &[&str]) -> Option<(usize, usize)> {
+    let mut start = 0;
+    let mut end = 0;
+    for pattern in patterns {
+        start = end + content[end..].find(pattern)?;
+        end = start + pattern.len();
+    }
+    let preceding = &content[..start];
+    let line_start = preceding.rfind('\n').map_or(0, |index| index + 1);
+    Some((
+        preceding.matches('\n').count() + 1,
+        preceding[line_start..].chars().count() + 1,
+    ))
+}
+
+/// Dependency location record written to the locations file.
+#[derive(Debug, Clone, serde::Serialize)]
+struct DependencyLocation<'a> {
+    name: &'a str,
+    version: Option<&'a str>,
+    registry_host_name: &'a str,
+    path: &'a std::path::PathBuf,
+    line: Option<usize>,
+    column: Option<usize>,
+    key_path: Option<&'a str>,
+}
+
+/// Write the location of each dependency to a JSON file.
+pub fn write_locations_file(
+    file_path: &std::path::PathBuf,
+    all_file_defined_dependencies: &Vec<FileDefinedDependencies>,
+) -> Result<()> {
+    let mut dependency_locations = Vec::new();
+    for file_defined_dependencies in all_file_defined_dependencies {
+        let mut dependencies: Vec<_> = file_defined_dependencies.dependencies.iter().collect();
+        dependencies.sort_by_key(|(_, location)| (location.line, location.column));
+        for (dependency, location) in dependencies {
+            dependency_locations.push(DependencyLocation {
+                name: &dependency.name,
+                version: dependency.version.as_ref().ok().map(|v| v.as_str()),
+                registry_host_name: &file_defined_dependencies.registry_host_name,
+                path: &location.path,
+                line: location.line,
+                column: location.column,
+                key_path: location.key_path.as_deref(),
+            });
+        }
+    }
+
+    let file = std::fs::File::create(&file_path).context(format!(
+        "Failed to create locations file: {}",
+        file_path.display()
+    ))?;
+    serde_json::to_writer_pretty(file, &dependency_locations)?;
+    Ok(())
+}
+
+/// Convert into file defined dependencies without locations.
+pub fn into_file_defined_dependencies(
+    all_file_defined_dependencies: Vec<FileDefinedDependencies>,
+) -> Vec<vouch_lib::extension::FileDefinedDependencies> {
+    all_file_defined_dependencies
+        .into_iter()
+        .map(
+            |file_defined_dependencies| vouch_lib::extension::FileDefinedDependencies {
+                path: file_defined_dependencies.path,
+                registry_host_name: file_defined_dependencies.registry_host_name,
+                dependencies: file_defined_dependencies.dependencies.into_keys().collect(),
+            },
+        )
+        .collect()
+}
@@ -0,0 +1,470 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeSet;
+
+use crate::{environment, pep440, requirements};
+
+/// Environment marker variable names.
+pub static VARIABLES: &[&str] = &[
+    "python_version",
+    "python_full_version",
+    "os_name",
+    "sys_platform",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "platform_machine",
+    "platform_python_implementation",
+    "implementation_name",
+    "implementation_version",
+    "extra",
+    "extras",
+    "dependency_groups",
+];
+
+/// Set valued variable names, which are only defined when evaluating lock file (PEP 751)
+/// markers (e.g. `'dev' in dependency_groups`).
+static SET_VARIABLES: &[&str] = &["extras", "dependency_groups"];
+
+/// Legacy variable names and their PEP 508 equivalents.
+static LEGACY_VARIABLES: &[(&str, &str)] = &[
+    ("os.name", "os_name"),
+    ("sys.platform", "sys_platform"),
+    ("platform.version", "platform_version"),
+    ("platform.machine", "platform_machine"),
+    (
+        "platform.python_implementation",
+        "platform_python_implementation",
+    ),
+    ("python_implementation", "platform_python_implementation"),
+];
+
+static OPERATORS: &[&str] = &["===", "==", "!=", "<=", ">=", "~=", "<", ">"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    String(String),
+    Operator(String),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+fn tokenize(marker: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = marker.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {}
+            '(' => tokens.push(Token::OpenParenthesis),
+            ')' => tokens.push(Token::CloseParenthesis),
+            '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => break,
+                        Some(next) => value.push(next),
+                        None => return Err(format_err!("Unterminated string in marker.")),
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            '<' | '>' | '=' | '!' | '~' => {
+                let mut operator = c.to_string();
+                while let Some(next) = chars.peek().filter(|next| "<>=!~".contains(**next)) {
+                    operator.push(*next);
+                    chars.next();
+                }
+                if !OPERATORS.contains(&operator.as_str()) {
+                    return Err(format_err!("Unknown marker operator: {}", operator));
+                }
+                tokens.push(Token::Operator(operator));
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut name = c.to_string();
+                while let Some(next) = chars
+                    .peek()
+                    .filter(|next| next.is_alphanumeric() || **next == '_' || **next == '.')
+                {
+                    name.push(*next);
+                    chars.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            _ => return Err(format_err!("Unexpected character in marker: {}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Comparison operand.
+#[derive(Debug, Clone)]
+enum Value {
+    Variable(String),
+    String(String),
+}
+
+/// Parsed environment marker expression.
+#[derive(Debug, Clone)]
+enum Marker {
+    Comparison {
+        left: Value,
+        operator: String,
+        right: Value,
+    },
+    And(Box<Marker>, Box<Marker>),
+    Or(Box<Marker>, Box<Marker>),
+}
+
+/// Recursive descent parser of the PEP 508 marker grammar.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(format_err!("Unexpected end of marker."))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.peek() == Some(&Token::Name(keyword.to_string()))
+    }
+
+    fn parse_or(&mut self) -> Result<Marker> {
+        let mut marker = self.parse_and()?;
+        while self.is_keyword("or") {
+            self.position += 1;
+            marker = Marker::Or(Box::new(marker), Box::new(self.parse_and()?));
+        }
+        Ok(marker)
+    }
+
+    fn parse_and(&mut self) -> Result<Marker> {
+        let mut marker = self.parse_atom()?;
+        while self.is_keyword("and") {
+            self.position += 1;
+            marker = Marker::And(Box::new(marker), Box::new(self.parse_atom()?));
+        }
+        Ok(marker)
+    }
+
+    fn parse_atom(&mut self) -> Result<Marker> {
+        if self.peek() == Some(&Token::OpenParenthesis) {
+            self.position += 1;
+            let marker = self.parse_or()?;
+            if self.next()? != Token::CloseParenthesis {
+                return Err(format_err!("Expected closing parenthesis in marker."));
+            }
+            return Ok(marker);
+        }
+        let left = self.parse_value()?;
+        let operator = match self.next()? {
+            Token::Operator(operator) => operator,
+            Token::Name(name) if name == "in" => name,
+            Token::Name(name) if name == "not" => match self.next()? {
+                Token::Name(next) if next == "in" => "not in".to_string(),
+                _ => return Err(format_err!("Expected 'in' after 'not' in marker.")),
+            },
+            token => return Err(format_err!("Expected marker operator, found: {:?}", token)),
+        };
+        let right = self.parse_value()?;
+        Ok(Marker::Comparison {
+            left,
+            operator,
+            right,
+        })
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.next()? {
+            Token::String(value) => Ok(Value::String(value)),
+            Token::Name(name) => {
+                let name = LEGACY_VARIABLES
+                    .iter()
+                    .find(|(legacy_name, _)| *legacy_name == name)
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or(name);
+                if !VARIABLES.contains(&name.as_str()) {
+                    return Err(format_err!("Unknown marker variable: {}", name));
+                }
+                Ok(Value::Variable(name))
+            }
+            token => Err(format_err!("Expected marker value, found: {:?}", token)),
+        }
+    }
+}
+
+/// Compare marker values.
+///
+/// Values are compared as PEP 440 versions where possible, otherwise as strings.
+fn compare(left: &str, operator: &str, right: &str) -> Result<bool> {
+    match operator {
+        "in" => return Ok(right.contains(left)),
+        "not in" => return Ok(!right.contains(left)),
+        _ => {}
+    }
+    let specifier = pep440::Specifier::parse(&format!("{}{}", operator, right));
+    if let (Ok(specifier), Ok(version)) = (specifier, pep440::Version::parse(&left)) {
+        return Ok(specifier.contains(&version));
+    }
+    match operator {
+        "==" | "===" => Ok(left == right),
+        "!=" => Ok(left != right),
+        "<" => Ok(left < right),
+        "<=" => Ok(left <= right),
+        ">" => Ok(left > right),
+        ">=" => Ok(left >= right),
+        _ => Err(format_err!(
+            "Failed to compare non-version marker values: '{}' {} '{}'",
+            left,
+            operator,
+            right
+        )),
+    }
+}
+
+/// Returns true if the set variable contains the value, for `in` and `not in` comparisons.
+///
+/// Names are compared normalized.
+fn compare_set(value: &Value, operator: &str, set: &BTreeSet<String>) -> Result<bool> {
+    let value = match value {
+        Value::String(value) => requirements::normalize_name(&value),
+        Value::Variable(name) => {
+            return Err(format_err!(
+                "Expected string to compare with set variable, found: {}",
+                name
+            ))
+        }
+    };
+    match operator {
+        "in" => Ok(set.contains(&value)),
+        "not in" => Ok(!set.contains(&value)),
+        _ => Err(format_err!(
+            "Set variables only support 'in' and 'not in' comparisons, found: {}",
+            operator
+        )),
+    }
+}
+
+impl Marker {
+    fn evaluate(
+        &self,
+        environment: &environment::Environment,
+        extras: &BTreeSet<String>,
+        lock_variables: &Option<(&BTreeSet<String>, &BTreeSet<String>)>,
+    ) -> Result<bool> {
+        match self {
+            Self::And(left, right) => Ok(left.evaluate(&environment, &extras, &lock_variables)?
+                && right.evaluate(&environment, &extras, &lock_variables)?),
+            Self::Or(left, right) => Ok(left.evaluate(&environment, &extras, &lock_variables)?
+                || right.evaluate(&environment, &extras, &lock_variables)?),
+            Self::Comparison {
+                left,
+                operator,
+                right,
+            } => {
+                if let Value::Variable(name) = right {
+                    if SET_VARIABLES.contains(&name.as_str()) {
+                        let (lock_extras, lock_dependency_groups) =
+                            lock_variables.ok_or(format_err!(
+                                "Set variable '{}' is only defined for lock file markers.",
+                                name
+                            ))?;
+                        let set = if name == "extras" {
+                            lock_extras
+                        } else {
+                            lock_dependency_groups
+                        };
+                        return compare_set(&left, &operator, &set);
+                    }
+                }
+                if let Value::Variable(name) = left {
+                    if SET_VARIABLES.contains(&name.as_str()) {
+                        return Err(format_err!(
+                            "Set variable '{}' must be the right hand side of a comparison.",
+                            name
+                        ));
+                    }
+                }
+                // The `extra` variable takes the value of each requested extra in turn. Extra
+                // names are compared normalized.
+                let is_extra_comparison = [left, right]
+                    .iter()
+                    .any(|value| matches!(value, Value::Variable(name) if name == "extra"));
+                let extra_values: Vec<String> = if extras.is_empty() {
+                    vec![String::new()]
+                } else {
+                    extras.iter().cloned().collect()
+                };
+                let get_value = |value: &Value, extra: &str| match value {
+                    Value::Variable(name) if name == "extra" => extra.to_string(),
+                    Value::Variable(name) => environment
+                        .marker_variables
+                        .get(name)
+                        .cloned()
+                        .unwrap_or_default(),
+                    Value::String(value) if is_extra_comparison => {
+                        requirements::normalize_name(&value)
+                    }
+                    Value::String(value) => value.clone(),
+                };
+                for extra in &extra_values {
+                    if compare(
+                        &get_value(left, &extra),
+                        &operator,
+                        &get_value(right, &extra),
+                    )? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Returns true if the environment marker holds within the target environment.
+///
+/// Example marker: `python_version < "3.8" and sys_platform == "win32"`. Markers which compare
+/// the `extra` variable hold if any of the given extras satisfy the comparison.
+pub fn evaluate(
+    marker: &str,
+    environment: &environment::Environment,
+    extras: &BTreeSet<String>,
+) -> Result<bool> {
+    evaluate_marker(&marker, &environment, &extras, &None)
+}
+
+/// Returns true if the lock file (PEP 751) environment marker holds within the target
+/// environment.
+///
+/// Lock file markers may also test the installed extras and dependency groups through the
+/// `extras` and `dependency_groups` set variables. Example marker: `'dev' in dependency_groups`.
+pub fn evaluate_lock(
+    marker: &str,
+    environment: &environment::Environment,
+    extras: &BTreeSet<String>,
+    dependency_groups: &BTreeSet<String>,
+) -> Result<bool> {
+    evaluate_marker(
+        &marker,
+        &environment,
+        &BTreeSet::new(),
+        &Some((&extras, &dependency_groups)),
+    )
+}
+
+fn evaluate_marker(
+    marker: &str,
+    environment: &environment::Environment,
+    extras: &BTreeSet<String>,
+    lock_variables: &Option<(&BTreeSet<String>, &BTreeSet<String>)>,
+) -> Result<bool> {
+    let mut parser = Parser {
+        tokens: tokenize(&marker).context(format!("Failed to parse marker: {}", marker))?,
+        position: 0,
+    };
+    let parsed_marker = parser
+        .parse_or()
+        .context(format!("Failed to parse marker: {}", marker))?;
+    if parser.position != parser.tokens.len() {
+        return Err(format_err!(
+            "Unexpected trailing tokens in marker: {}",
+            marker
+        ));
+    }
+    parsed_marker
+        .evaluate(&environment, &extras, &lock_variables)
+        .context(format!("Failed to evaluate marker: {}", marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate_on(marker: &str, python_version: &str, platform: &str) -> Result<bool> {
+        let environment =
+            environment::Environment::new(&python_version, &platform, "x86_64", "cpython")?;
+        evaluate(&marker, &environment, &BTreeSet::new())
+    }
+
+    #[test]
+    fn version_comparisons() {
+        assert!(!evaluate_on("python_version < \"3.7\"", "3.12", "linux").unwrap());
+        assert!(evaluate_on("python_version < \"3.7\"", "3.6", "linux").unwrap());
+        assert!(evaluate_on("python_version >= '3.8'", "3.12", "linux").unwrap());
+        assert!(evaluate_on("python_full_version == '3.12.0'", "3.12", "linux").unwrap());
+        assert!(evaluate_on("python_version ~= '3.10'", "3.12", "linux").unwrap());
+        assert!(evaluate_on("'3.8' < python_version", "3.12", "linux").unwrap());
+    }
+
+    #[test]
+    fn string_comparisons() {
+        assert!(!evaluate_on("sys_platform == \"win32\"", "3.12", "linux").unwrap());
+        assert!(evaluate_on("sys_platform == \"win32\"", "3.12", "win32").unwrap());
+        assert!(evaluate_on("os_name == 'nt'", "3.12", "win32").unwrap());
+        assert!(evaluate_on("'linux' in sys_platform", "3.12", "linux").unwrap());
+        assert!(evaluate_on("'win' not in sys_platform", "3.12", "linux").unwrap());
+        assert!(evaluate_on("sys.platform == 'linux'", "3.12", "linux").unwrap());
+        assert!(evaluate_on("implementation_name == 'cpython'", "3.12", "linux").unwrap());
+    }
+
+    #[test]
+    fn boolean_operators() {
+        let marker = "python_version < '3.8' and sys_platform == 'win32' or os_name == 'posix'";
+        assert!(evaluate_on(&marker, "3.12", "linux").unwrap());
+        assert!(evaluate_on(&marker, "3.6", "win32").unwrap());
+        assert!(!evaluate_on(&marker, "3.12", "win32").unwrap());
+
+        let marker = "python_version < '3.8' and (sys_platform == 'win32' or os_name == 'posix')";
+        assert!(!evaluate_on(&marker, "3.12", "linux").unwrap());
+        assert!(evaluate_on(&marker, "3.6", "linux").unwrap());
+    }
+
+    #[test]
+    fn extras() {
+        let environment =
+            environment::Environment::new("3.12", "linux", "x86_64", "cpython").unwrap();
+        let extras: BTreeSet<String> = vec!["socks".to_string(), "http2".to_string()]
+            .into_iter()
+            .collect();
+        assert!(evaluate("extra == 'socks'", &environment, &extras).unwrap());
+        assert!(evaluate("extra == \"Socks\"", &environment, &extras).unwrap());
+        assert!(!evaluate("extra == 'brotli'", &environment, &extras).unwrap());
+        assert!(!evaluate("extra == 'socks'", &environment, &BTreeSet::new()).unwrap());
+    }
+
+    #[test]
+    fn lock_file_set_variables() {
+        let environment =
+            environment::Environment::new("3.12", "linux", "x86_64", "cpython").unwrap();
+        let groups: BTreeSet<String> = vec!["dev".to_string()].into_iter().collect();
+        let extras: BTreeSet<String> = vec!["socks".to_string()].into_iter().collect();
+        let marker = "'dev' in dependency_groups and python_version >= '3.8'";
+        assert!(evaluate_lock(&marker, &environment, &BTreeSet::new(), &groups).unwrap());
+        assert!(!evaluate_lock(&marker, &environment, &BTreeSet::new(), &BTreeSet::new()).unwrap());
+        assert!(evaluate_lock("'Socks' in extras", &environment, &extras, &groups).unwrap());
+        assert!(evaluate_lock("'docs' not in extras", &environment, &extras, &groups).unwrap());
+        assert!(evaluate_lock("extras == 'socks'", &environment, &extras, &groups).is_err());
+        assert!(evaluate("'dev' in dependency_groups", &environment, &BTreeSet::new()).is_err());
+    }
+
+    #[test]
+    fn invalid_markers() {
+        assert!(evaluate_on("unknown_variable == '1'", "3.12", "linux").is_err());
+        assert!(evaluate_on("sys_platform ~= 'linux'", "3.12", "linux").is_err());
+        assert!(evaluate_on("sys_platform == 'linux' linux", "3.12", "linux").is_err());
+        assert!(evaluate_on("(sys_platform == 'linux'", "3.12", "linux").is_err());
+        assert!(evaluate_on("sys_platform == 'linux", "3.12", "linux").is_err());
+    }
+}
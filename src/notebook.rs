@@ -0,0 +1,150 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{environment, location, pip, requirements, source};
+
+/// Returns the index and source code of each code cell within the notebook.
+///
+/// Cell source may be given as a string or as an array of line strings.
+fn get_code_cells(notebook: &serde_json::Value) -> Result<Vec<(usize, String)>> {
+    let cells = notebook["cells"]
+        .as_array()
+        .ok_or(format_err!("Failed to parse notebook cells array."))?;
+
+    let mut code_cells = Vec::new();
+    for (index, cell) in cells.iter().enumerate() {
+        if cell["cell_type"].as_str() != Some("code") {
+            continue;
+        }
+        let source = match &cell["source"] {
+            serde_json::Value::String(v) => v.clone(),
+            serde_json::Value::Array(lines) => lines
+                .iter()
+                .filter_map(|line| line.as_str())
+                .collect::<Vec<_>>()
+                .join(""),
+            _ => return Err(format_err!("Failed to parse notebook cell source.")),
+        };
+        code_cells.push((index, source));
+    }
+    Ok(code_cells)
+}
+
+/// Returns shell command lines from `%pip` magic and `!` shell escape lines.
+///
+/// Lines ending with a backslash are joined with the following line.
+fn get_command_lines(cell_source: &str) -> Vec<String> {
+    let mut command_lines = Vec::new();
+    let mut current_line: Option<String> = None;
+    for line in cell_source.lines() {
+        let line = line.trim();
+        let line = match current_line.take() {
+            Some(previous) => previous + " " + line,
+            None => {
+                let command = line
+                    .strip_prefix("%pip")
+                    .map(|v| format!("pip{}", v))
+                    .or(line.strip_prefix('!').map(|v| v.to_string()));
+                match command {
+                    Some(v) => v,
+                    None => continue,
+                }
+            }
+        };
+        match line.strip_suffix('\\') {
+            Some(v) => current_line = Some(v.to_string()),
+            None => command_lines.push(line),
+        }
+    }
+    command_lines.extend(current_line);
+    command_lines
+}
+
+/// Parse dependencies from `%pip install` and `!pip install` commands within notebook code cells.
+///
+/// Returns one file defined dependencies structure per registry host. Requirements files
+/// installed with `-r` are relative to the notebook.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let notebook: serde_json::Value = serde_json::from_str(&content)
+        .context(format!("Failed to parse notebook: {}", file_path.display()))?;
+    let code_cells = get_code_cells(&notebook)
+        .context(format!("Failed to parse notebook: {}", file_path.display()))?;
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (cell_index, command_line) in code_cells.iter().flat_map(|(index, cell)| {
+        get_command_lines(&cell)
+            .into_iter()
+            .map(move |v| (*index, v))
+    }) {
+        // Cells are located by counting `cell_type` keys, which precede cell sources.
+        let cell_patterns = vec!["\"cell_type\""; cell_index + 1];
+        for install_command in pip::parse_install_commands(&command_line) {
+            let host_name = match &install_command.index_url {
+                Some(url) => source::get_index_host_name(&url)?,
+                None => source::PYPI_HOST_NAME.to_string(),
+            };
+            for requirement_string in &install_command.requirement_strings {
+                let dependency = pip::get_dependency(&requirement_string, &host_name, &environment)
+                    .context(format!(
+                        "Failed to parse pip install argument '{}' in notebook: {}",
+                        requirement_string,
+                        file_path.display()
+                    ))?;
+                if let Some((dependency_host_name, dependency)) = dependency {
+                    let mut patterns = cell_patterns.clone();
+                    patterns.extend(&["\"source\"", requirement_string]);
+                    let location = location::Location::find(&file_path, &content, &patterns)
+                        .with_key_path(&format!("cells[{}].source", cell_index));
+                    all_dependencies
+                        .entry(dependency_host_name)
+                        .or_insert_with(location::Dependencies::new)
+                        .insert(dependency, location);
+                }
+            }
+
+            for requirements_file in &install_command.requirements_files {
+                let requirements_file_path = file_path
+                    .parent()
+                    .map(|parent| parent.join(&requirements_file))
+                    .unwrap_or(std::path::PathBuf::from(&requirements_file));
+                if requirements_file_path.is_file() {
+                    requirements_file_paths.push(requirements_file_path);
+                    continue;
+                }
+                let mut patterns = cell_patterns.clone();
+                patterns.extend(&["\"source\"", requirements_file]);
+                all_dependencies
+                    .entry(host_name.clone())
+                    .or_insert_with(location::Dependencies::new)
+                    .insert(
+                        requirements::to_missing_file_dependency(&requirements_file),
+                        location::Location::find(&file_path, &content, &patterns)
+                            .with_key_path(&format!("cells[{}].source", cell_index)),
+                    );
+            }
+        }
+    }
+
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+/// Returns Jupyter notebooks found within the given directory.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_notebook = path.extension().map_or(false, |e| e == "ipynb");
+            if path.is_file() && is_notebook {
+                file_paths.push(path);
+            }
+        }
+    }
+    file_paths.sort();
+    file_paths
+}
@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+use crate::tokenizer::Token;
+use crate::{environment, location, pip, requirements, source, tokenizer, version_error};
+
+/// `install` call within a nox session function.
+#[derive(Debug, Clone)]
+struct InstallCall {
+    session_name: Option<String>,
+    // None if any positional argument is not a string literal.
+    arguments: Option<Vec<String>>,
+}
+
+/// Parse `install` call positional arguments starting after the opening parenthesis.
+///
+/// Returns None if any positional argument is not a string literal. Keyword arguments are
+/// ignored.
+fn parse_install_arguments(tokens: &Vec<Token>, start: usize) -> Option<Vec<String>> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut is_keyword_argument = false;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Operator('(') | Token::Operator('[') | Token::Operator('{') => {
+                if depth == 0 && !is_keyword_argument {
+                    return None;
+                }
+                depth += 1;
+            }
+            Token::Operator(')') | Token::Operator(']') | Token::Operator('}') => {
+                if depth == 0 {
+                    return Some(arguments);
+                }
+                depth -= 1;
+            }
+            Token::Operator(',') if depth == 0 => is_keyword_argument = false,
+            _ if depth > 0 || is_keyword_argument => {}
+            Token::Name(_) if tokens.get(index + 1) == Some(&Token::Operator('=')) => {
+                is_keyword_argument = true;
+            }
+            Token::String(v) => arguments.push(v.clone()),
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Returns `.install(...)` calls (e.g. `session.install("pytest")`) within the noxfile.
+fn get_install_calls(tokens: &Vec<Token>) -> Vec<InstallCall> {
+    let mut install_calls = Vec::new();
+    let mut session_name = None;
+    for (index, token) in tokens.iter().enumerate() {
+        if *token == Token::Name("def".to_string()) {
+            if let Some(Token::Name(name)) = tokens.get(index + 1) {
+                session_name = Some(name.clone());
+            }
+            continue;
+        }
+        let is_install_call = *token == Token::Operator('.')
+            && tokens.get(index + 1) == Some(&Token::Name("install".to_string()))
+            && tokens.get(index + 2) == Some(&Token::Operator('('));
+        if is_install_call {
+            install_calls.push(InstallCall {
+                session_name: session_name.clone(),
+                arguments: parse_install_arguments(&tokens, index + 3),
+            });
+        }
+    }
+    install_calls
+}
+
+/// Parse dependencies from `session.install` calls within a noxfile.
+///
+/// The file is never executed. Returns one file defined dependencies structure per session and
+/// registry host, labelled with the session function name (e.g. `noxfile.py[lint]`). Calls with
+/// arguments computed dynamically are reported as a dependency named `session.install` with an
+/// error. Requirements files installed with `-r` are relative to the noxfile.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let tokens = tokenizer::tokenize(&content);
+    let directory = file_path
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .to_path_buf();
+
+    let mut all_dependencies: BTreeMap<(Option<String>, String), location::Dependencies> =
+        BTreeMap::new();
+    for install_call in get_install_calls(&tokens) {
+        let session_pattern = install_call
+            .session_name
+            .as_ref()
+            .map(|name| format!("def {}", name))
+            .unwrap_or_default();
+        let arguments = match install_call.arguments {
+            Some(v) => v,
+            None => {
+                all_dependencies
+                    .entry((
+                        install_call.session_name,
+                        source::PYPI_HOST_NAME.to_string(),
+                    ))
+                    .or_insert_with(location::Dependencies::new)
+                    .insert(
+                        vouch_lib::extension::Dependency {
+                            name: "session.install".to_string(),
+                            version: Err(
+                                vouch_lib::extension::common::VersionError::from_parse_error(
+                                    version_error::DYNAMIC_DEPENDENCIES_ERROR,
+                                ),
+                            ),
+                        },
+                        location::Location::find(
+                            &file_path,
+                            &content,
+                            &[&session_pattern, ".install("],
+                        ),
+                    );
+                continue;
+            }
+        };
+
+        let install_command = pip::parse_install_arguments(&arguments);
+        let host_name = match &install_command.index_url {
+            Some(url) => source::get_index_host_name(&url)?,
+            None => source::PYPI_HOST_NAME.to_string(),
+        };
+        for requirement_string in &install_command.requirement_strings {
+            let dependency = pip::get_dependency(&requirement_string, &host_name, &environment)
+                .context(format!(
+                    "Failed to parse session install argument '{}' in file: {}",
+                    requirement_string,
+                    file_path.display()
+                ))?;
+            if let Some((dependency_host_name, dependency)) = dependency {
+                let location = location::Location::find(
+                    &file_path,
+                    &content,
+                    &[&session_pattern, &requirement_string],
+                );
+                all_dependencies
+                    .entry((install_call.session_name.clone(), dependency_host_name))
+                    .or_insert_with(location::Dependencies::new)
+                    .insert(dependency, location);
+            }
+        }
+        for requirements_file in install_command.requirements_files {
+            let requirements_file_path = directory.join(&requirements_file);
+            if requirements_file_path.is_file() {
+                requirements_file_paths.push(requirements_file_path);
+                continue;
+            }
+            all_dependencies
+                .entry((install_call.session_name.clone(), host_name.clone()))
+                .or_insert_with(location::Dependencies::new)
+                .insert(
+                    requirements::to_missing_file_dependency(&requirements_file),
+                    location::Location::find(
+                        &file_path,
+                        &content,
+                        &[&session_pattern, &requirements_file],
+                    ),
+                );
+        }
+    }
+
+    let mut all_file_defined_dependencies = Vec::new();
+    for ((session_name, registry_host_name), dependencies) in all_dependencies {
+        let path = match session_name {
+            Some(name) => std::path::PathBuf::from(format!("{}[{}]", file_path.display(), name)),
+            None => file_path.clone(),
+        };
+        all_file_defined_dependencies.push(location::FileDefinedDependencies {
+            path: path,
+            registry_host_name: registry_host_name,
+            dependencies: dependencies,
+        });
+    }
+    Ok(all_file_defined_dependencies)
+}
@@ -0,0 +1,285 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Read;
+
+use crate::{
+    arguments, environment, marker, requirements, resolver, source, version_error, warning,
+};
+
+/// Returns the PyPI JSON API entry of the given package release.
+///
+/// Returns the entry of the latest release if no version is given.
+fn get_release_json(
+    package_name: &str,
+    package_version: &Option<&str>,
+) -> Result<serde_json::Value> {
+    let url = match package_version {
+        Some(version) => format!(
+            "https://{}/pypi/{}/{}/json",
+            source::PYPI_HOST_NAME,
+            package_name,
+            version
+        ),
+        None => format!(
+            "https://{}/pypi/{}/json",
+            source::PYPI_HOST_NAME,
+            package_name
+        ),
+    };
+    let mut result = reqwest::blocking::get(&url)?;
+    if !result.status().is_success() {
+        return Err(format_err!(
+            "Failed to find package release ({}): {}",
+            result.status(),
+            url
+        ));
+    }
+    let mut body = String::new();
+    result.read_to_string(&mut body)?;
+
+    Ok(serde_json::from_str(&body).context(format!("JSON was not well-formatted:\n{}", body))?)
+}
+
+/// Returns the `info.requires_dist` requirement strings of a PyPI JSON API entry.
+///
+/// Returns `None` for releases without dependency metadata, which have a null `requires_dist`
+/// field. Their dependencies are unknown rather than empty.
+fn get_requires_dist(json: &serde_json::Value) -> Result<Option<Vec<String>>> {
+    let requires_dist = match json["info"]["requires_dist"].as_array() {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let mut entries = Vec::new();
+    for entry in requires_dist {
+        let entry = entry
+            .as_str()
+            .ok_or(format_err!("Failed to parse 'requires_dist' entry."))?;
+        entries.push(entry.to_string());
+    }
+    Ok(Some(entries))
+}
+
+/// Returns requested extras, version specifier and, if requested, environment marker of the
+/// requirement.
+///
+/// Example: `[socks]>=1.5.6,!=1.5.7; extra == "socks"`
+fn get_requirement_details(
+    requirement: &requirements::Requirement,
+    include_marker: bool,
+) -> String {
+    let mut details = String::new();
+    if !requirement.extras.is_empty() {
+        details.push_str(&format!("[{}]", requirement.extras.join(",")));
+    }
+    match &requirement.url {
+        Some(url) => details.push_str(&format!("@ {}", url)),
+        None => details.push_str(&requirement.specifier),
+    }
+    if include_marker {
+        if let Some(marker) = &requirement.marker {
+            details.push_str(&format!("; {}", marker));
+        }
+    }
+    details
+}
+
+/// Convert a `requires_dist` requirement into a dependency.
+///
+/// Exactly pinned requirements are reported with their version, whether or not they request
+/// extras. The version error of other requirements details the requested extras (e.g.
+/// `[socks]>=1.5.6`).
+fn to_dependency(requirement: &requirements::Requirement) -> vouch_lib::extension::Dependency {
+    let mut dependency = requirements::to_dependency(&requirement);
+    if dependency.version.is_err() && !requirement.extras.is_empty() {
+        dependency.version = Err(
+            vouch_lib::extension::common::VersionError::from_parse_error(&get_requirement_details(
+                &requirement,
+                false,
+            )),
+        );
+    }
+    dependency
+}
+
+/// Convert a `requires_dist` requirement whose environment marker does not hold into a
+/// dependency.
+///
+/// The version error details the extras, version specifier and marker (e.g.
+/// `>=1.5.6; extra == "socks"`), so that optional dependencies are surfaced rather than dropped.
+fn to_inapplicable_dependency(
+    requirement: &requirements::Requirement,
+) -> vouch_lib::extension::Dependency {
+    vouch_lib::extension::Dependency {
+        name: requirement.name.clone(),
+        version: Err(
+            vouch_lib::extension::common::VersionError::from_parse_error(&get_requirement_details(
+                &requirement,
+                true,
+            )),
+        ),
+    }
+}
+
+/// Returns the normalized package extras requested by the extension arguments.
+fn get_extras(arguments: &arguments::Arguments) -> BTreeSet<String> {
+    arguments
+        .extras
+        .iter()
+        .map(|extra| requirements::normalize_name(&extra))
+        .collect()
+}
+
+/// Returns the dependencies of a PyPI package release.
+///
+/// Dependencies are read from the `info.requires_dist` field of the PyPI JSON API. The latest
+/// release is used if no version is given. Releases without dependency metadata are reported as
+/// a single dependency on the package itself, with a version error. Requirements whose
+/// environment marker does not hold
+/// within the target environment, given the requested extras, are reported with a version error
+/// which names the marker.
+pub fn get_package_dependencies(
+    package_name: &str,
+    package_version: &Option<&str>,
+    arguments: &arguments::Arguments,
+) -> Result<vouch_lib::extension::PackageDependencies> {
+    let environment = environment::Environment::from_arguments(&arguments)?;
+    let extras = get_extras(&arguments);
+    let json = get_release_json(&package_name, &package_version)?;
+    let version = match json["info"]["version"].as_str() {
+        Some(v) => Ok(v.to_string()),
+        None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+    };
+
+    let requires_dist = match get_requires_dist(&json)? {
+        Some(v) => v,
+        None => {
+            return Ok(vouch_lib::extension::PackageDependencies {
+                package_version: version,
+                registry_host_name: source::PYPI_HOST_NAME.to_string(),
+                dependencies: vec![vouch_lib::extension::Dependency {
+                    name: package_name.to_string(),
+                    version: Err(
+                        vouch_lib::extension::common::VersionError::from_parse_error(
+                            version_error::MISSING_METADATA_ERROR,
+                        ),
+                    ),
+                }],
+            });
+        }
+    };
+
+    let mut dependencies = HashSet::new();
+    for entry in requires_dist {
+        let requirement = requirements::parse_requirement(&entry).context(format!(
+            "Failed to parse 'requires_dist' entry '{}' of package: {}",
+            entry, package_name
+        ))?;
+        if let Some(requirement_marker) = &requirement.marker {
+            if !marker::evaluate(&requirement_marker, &environment, &extras).context(format!(
+                "Failed to evaluate 'requires_dist' entry '{}' of package: {}",
+                entry, package_name
+            ))? {
+                dependencies.insert(to_inapplicable_dependency(&requirement));
+                continue;
+            }
+        }
+        dependencies.insert(to_dependency(&requirement));
+    }
+
+    Ok(vouch_lib::extension::PackageDependencies {
+        package_version: version,
+        registry_host_name: source::PYPI_HOST_NAME.to_string(),
+        dependencies: dependencies.into_iter().collect(),
+    })
+}
+
+/// PyPI JSON API package registry.
+pub struct PypiRegistry;
+
+impl resolver::Registry for PypiRegistry {
+    fn get_releases(
+        &self,
+        package_name: &str,
+    ) -> Result<BTreeMap<String, Vec<resolver::ReleaseFile>>> {
+        let json = get_release_json(&package_name, &None)?;
+        let releases = json["releases"]
+            .as_object()
+            .ok_or(format_err!("Failed to find releases JSON section."))?;
+
+        let mut all_release_files = BTreeMap::new();
+        for (version, files) in releases {
+            let files = files.as_array().ok_or(format_err!(
+                "Failed to parse release files of version: {}",
+                version
+            ))?;
+            let release_files = files
+                .iter()
+                .map(|file| resolver::ReleaseFile {
+                    filename: file["filename"].as_str().unwrap_or_default().to_string(),
+                    package_type: file["packagetype"].as_str().unwrap_or_default().to_string(),
+                    requires_python: file["requires_python"].as_str().map(|v| v.to_string()),
+                    is_yanked: file["yanked"].as_bool().unwrap_or(false),
+                })
+                .collect();
+            all_release_files.insert(version.clone(), release_files);
+        }
+        Ok(all_release_files)
+    }
+
+    fn get_requires_dist(&self, package_name: &str, package_version: &str) -> Result<Vec<String>> {
+        get_requires_dist(&get_release_json(&package_name, &Some(package_version))?)?.ok_or(
+            format_err!(
+                "Failed to find dependency metadata (null 'requires_dist') of package release: \
+                 {} {}",
+                package_name,
+                package_version
+            ),
+        )
+    }
+}
+
+/// Returns the complete pinned dependency tree of a PyPI package release.
+///
+/// Dependencies are resolved for the target environment given by the extension arguments.
+/// Dependency cycles are reported as warnings. The tree is written to the `tree_file` path, if
+/// given.
+pub fn get_transitive_package_dependencies(
+    package_name: &str,
+    package_version: &Option<&str>,
+    arguments: &arguments::Arguments,
+) -> Result<vouch_lib::extension::PackageDependencies> {
+    let environment = environment::Environment::from_arguments(&arguments)?;
+    let resolution = resolver::resolve(
+        &PypiRegistry,
+        &package_name,
+        &package_version,
+        &get_extras(&arguments),
+        &environment,
+    )?;
+    for cycle in &resolution.cycles {
+        warning::print(
+            &format!("Found dependency cycle: {}", cycle.join(" -> ")),
+            &arguments,
+        );
+    }
+    if let Some(tree_file_path) = &arguments.tree_file {
+        resolver::write_tree_file(&tree_file_path, &resolution)?;
+    }
+
+    let root = resolution.root().ok_or(format_err!(
+        "Code error: resolution is missing the requested package."
+    ))?;
+    Ok(vouch_lib::extension::PackageDependencies {
+        package_version: Ok(root.version.clone()),
+        registry_host_name: source::PYPI_HOST_NAME.to_string(),
+        dependencies: resolution
+            .packages
+            .values()
+            .filter(|package| package.name != resolution.name)
+            .map(|package| vouch_lib::extension::Dependency {
+                name: package.name.clone(),
+                version: Ok(package.version.clone()),
+            })
+            .collect(),
+    })
+}
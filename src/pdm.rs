@@ -0,0 +1,248 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::{arguments, environment, location, marker, pyproject, requirements, source};
+
+/// Returns registry host name and version for package.
+fn get_host_name_and_version(
+    package: &toml::Value,
+) -> Result<(String, vouch_lib::extension::common::VersionParseResult)> {
+    let version = match package.get("version").and_then(|v| v.as_str()) {
+        Some(v) => Ok(v.to_string()),
+        None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+    };
+
+    // Git sources are pinned by their resolved revision.
+    if let Some(url) = package.get("git").and_then(|v| v.as_str()) {
+        let version = match package.get("revision").and_then(|v| v.as_str()) {
+            Some(v) => Ok(v.to_string()),
+            None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+        };
+        return Ok((source::get_host_name(&url)?, version));
+    }
+    if package.get("path").is_some() {
+        return Ok((source::LOCAL_HOST_NAME.to_string(), version));
+    }
+    if let Some(url) = package.get("url").and_then(|v| v.as_str()) {
+        let host_name = if url.starts_with("file:") {
+            source::LOCAL_HOST_NAME.to_string()
+        } else {
+            source::get_host_name(&url)?
+        };
+        return Ok((host_name, version));
+    }
+
+    Ok((source::PYPI_HOST_NAME.to_string(), version))
+}
+
+/// Returns the set of packages which have file hashes within the lock file.
+///
+/// Lock files before version 4 list hashes within the `[metadata.files]` table, keyed by package
+/// name and version. Later versions list hashes within each package's `files` array.
+fn get_hashed_packages(lock: &toml::Value, packages: &Vec<toml::Value>) -> HashSet<String> {
+    let mut hashed_packages = HashSet::new();
+    if let Some(files) = lock
+        .get("metadata")
+        .and_then(|metadata| metadata.get("files"))
+        .and_then(|files| files.as_table())
+    {
+        for (key, files) in files {
+            let has_hashes = files.as_array().map_or(false, |files| {
+                files.iter().any(|file| file.get("hash").is_some())
+            });
+            if has_hashes {
+                hashed_packages.insert(key.clone());
+            }
+        }
+    }
+
+    for package in packages {
+        let has_hashes = package
+            .get("files")
+            .and_then(|files| files.as_array())
+            .map_or(false, |files| {
+                files.iter().any(|file| file.get("hash").is_some())
+            });
+        if let (true, Some(name), Some(version)) = (
+            has_hashes,
+            package.get("name").and_then(|v| v.as_str()),
+            package.get("version").and_then(|v| v.as_str()),
+        ) {
+            hashed_packages.insert(format!("{} {}", name, version));
+        }
+    }
+    hashed_packages
+}
+
+/// Returns the development group names of the project which the lock file belongs to.
+///
+/// Returns None if there is no pyproject.toml file beside the lock file.
+fn get_development_group_names(file_path: &std::path::PathBuf) -> Result<Option<BTreeSet<String>>> {
+    let pyproject_path = file_path.with_file_name("pyproject.toml");
+    if !pyproject_path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&pyproject_path)?;
+    let pyproject: toml::Value = toml::from_str(&content).context(format!(
+        "Failed to parse pyproject.toml: {}",
+        pyproject_path.display()
+    ))?;
+    Ok(Some(pyproject::get_development_group_names(&pyproject)))
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Returns one file defined dependencies structure per registry host. All locked groups listed within
+/// `[metadata].groups` are included. Registry packages without file hashes are reported with a
+/// version error because the locked artifacts can not be verified. Packages whose `marker` does
+/// not hold within the target environment are skipped. Packages which only belong to development
+/// groups, as defined by the project's pyproject.toml, are skipped if `production_only` is set.
+/// Optional dependency groups are production dependencies. Without a pyproject.toml file, all
+/// groups other than `default` are taken to be development groups.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let lock: toml::Value = toml::from_str(&content)
+        .context(format!("Failed to parse pdm.lock: {}", file_path.display()))?;
+
+    let packages = match lock.get("package") {
+        Some(v) => v
+            .as_array()
+            .ok_or(format_err!("Failed to parse 'package' tables of pdm.lock"))?
+            .clone(),
+        None => Vec::new(),
+    };
+    let locked_groups: Option<HashSet<&str>> = lock
+        .get("metadata")
+        .and_then(|metadata| metadata.get("groups"))
+        .and_then(|groups| groups.as_array())
+        .map(|groups| groups.iter().filter_map(|group| group.as_str()).collect());
+    let hashed_packages = get_hashed_packages(&lock, &packages);
+    let development_groups = if arguments.production_only {
+        get_development_group_names(&file_path)?
+    } else {
+        None
+    };
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        let name = package
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(format_err!("Failed to parse package name in pdm.lock"))?;
+        if let Some(package_marker) = package.get("marker").and_then(|v| v.as_str()) {
+            if !marker::evaluate(&package_marker, &environment, &BTreeSet::new())
+                .context(format!("Failed to evaluate marker of package: {}", name))?
+            {
+                continue;
+            }
+        }
+
+        // Older lock files name package groups "sections".
+        let package_groups = package
+            .get("groups")
+            .or(package.get("sections"))
+            .and_then(|groups| groups.as_array());
+        if let (Some(locked_groups), Some(package_groups)) = (&locked_groups, package_groups) {
+            let is_locked = package_groups
+                .iter()
+                .filter_map(|group| group.as_str())
+                .any(|group| locked_groups.contains(group));
+            if !is_locked {
+                continue;
+            }
+        }
+        if let (true, Some(package_groups)) = (arguments.production_only, package_groups) {
+            let is_production = package_groups
+                .iter()
+                .filter_map(|group| group.as_str())
+                .any(|group| match &development_groups {
+                    Some(development_groups) => {
+                        !development_groups.contains(&requirements::normalize_name(&group))
+                    }
+                    None => group == "default",
+                });
+            if !is_production {
+                continue;
+            }
+        }
+
+        let (host_name, mut version) = get_host_name_and_version(&package)
+            .context(format!("Failed to parse source of package: {}", name))?;
+        if let (true, Ok(v)) = (host_name == source::PYPI_HOST_NAME, &version) {
+            if !hashed_packages.contains(&format!("{} {}", name, v)) {
+                version = Err(
+                    vouch_lib::extension::common::VersionError::from_parse_error(&format!(
+                        "{} (no file hashes locked)",
+                        v
+                    )),
+                );
+            }
+        }
+
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: name.to_string(),
+                    version,
+                },
+                location::Location::find(&file_path, &content, &vec!["[[package]]"; index + 1])
+                    .with_key_path(&format!("package[{}]", index)),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_host_names() {
+        let lock: toml::Value = toml::from_str(
+            r#"
+            [[package]]
+            name = "requests"
+            version = "2.31.0"
+
+            [[package]]
+            name = "repo"
+            version = "1.0"
+            git = "https://github.com/owner/repo.git"
+            revision = "abc123"
+
+            [[package]]
+            name = "archive"
+            version = "1.0"
+            url = "https://example.com/archive-1.0.tar.gz"
+
+            [[package]]
+            name = "local"
+            version = "1.0"
+            path = "../local"
+            "#,
+        )
+        .unwrap();
+        let packages: Vec<(String, vouch_lib::extension::common::VersionParseResult)> = lock
+            ["package"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| get_host_name_and_version(&package).unwrap())
+            .collect();
+        assert_eq!(
+            packages,
+            vec![
+                ("pypi.org".to_string(), Ok("2.31.0".to_string())),
+                ("github.com".to_string(), Ok("abc123".to_string())),
+                ("example.com".to_string(), Ok("1.0".to_string())),
+                (source::LOCAL_HOST_NAME.to_string(), Ok("1.0".to_string())),
+            ]
+        );
+    }
+}
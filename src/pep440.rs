@@ -0,0 +1,591 @@
+use anyhow::{format_err, Result};
+use std::cmp::Ordering;
+
+/// Pre-release phase.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreRelease {
+    Alpha,
+    Beta,
+    ReleaseCandidate,
+}
+
+/// Local version label segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LocalSegment {
+    Number(u64),
+    String(String),
+}
+
+impl Ord for LocalSegment {
+    /// Numeric segments sort after alphanumeric segments.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Number(_), Self::String(_)) => Ordering::Greater,
+            (Self::String(_), Self::Number(_)) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for LocalSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// PEP 440 version.
+///
+/// Versions are normalized when parsed, e.g. `1.0-RC1` and `1.0rc1` are equal.
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub epoch: u64,
+    pub release: Vec<u64>,
+    pub pre: Option<(PreRelease, u64)>,
+    pub post: Option<u64>,
+    pub dev: Option<u64>,
+    local: Vec<LocalSegment>,
+}
+
+/// Version string cursor.
+struct Cursor<'a> {
+    value: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn remainder(&self) -> &'a str {
+        &self.value[self.position..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.position == self.value.len()
+    }
+
+    /// Consume an optional `.`, `-` or `_` separator.
+    fn skip_separator(&mut self) -> bool {
+        match self.remainder().chars().next() {
+            Some('.') | Some('-') | Some('_') => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consume the first matching prefix.
+    fn take_prefix(&mut self, prefixes: &[&str]) -> Option<&'a str> {
+        let remainder = self.remainder();
+        let prefix = prefixes
+            .iter()
+            .find(|prefix| remainder.starts_with(*prefix))?;
+        self.position += prefix.len();
+        Some(&remainder[..prefix.len()])
+    }
+
+    fn take_number(&mut self) -> Option<u64> {
+        let remainder = self.remainder();
+        let length = remainder
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(remainder.len());
+        if length == 0 {
+            return None;
+        }
+        self.position += length;
+        remainder[..length].parse().ok()
+    }
+
+    /// Consume an optional separator and number. Restores the position if there is no number.
+    fn take_separated_number(&mut self) -> Option<u64> {
+        let position = self.position;
+        self.skip_separator();
+        let number = self.take_number();
+        if number.is_none() {
+            self.position = position;
+        }
+        number
+    }
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Result<Self> {
+        let normalized = version.trim().to_lowercase();
+        let mut cursor = Cursor {
+            value: normalized.trim_start_matches('v'),
+            position: 0,
+        };
+        let error = || format_err!("Failed to parse version: {}", version);
+
+        let mut epoch = 0;
+        let mut release = vec![cursor.take_number().ok_or_else(error)?];
+        if cursor.remainder().starts_with('!') {
+            cursor.position += 1;
+            epoch = release[0];
+            release = vec![cursor.take_number().ok_or_else(error)?];
+        }
+        while cursor.remainder().starts_with('.') {
+            let position = cursor.position;
+            cursor.position += 1;
+            match cursor.take_number() {
+                Some(v) => release.push(v),
+                None => {
+                    cursor.position = position;
+                    break;
+                }
+            }
+        }
+
+        let mut pre = None;
+        let position = cursor.position;
+        cursor.skip_separator();
+        let phase = cursor.take_prefix(&["alpha", "beta", "preview", "pre", "rc", "a", "b", "c"]);
+        match phase {
+            Some(phase) => {
+                let phase = match phase {
+                    "alpha" | "a" => PreRelease::Alpha,
+                    "beta" | "b" => PreRelease::Beta,
+                    _ => PreRelease::ReleaseCandidate,
+                };
+                pre = Some((phase, cursor.take_separated_number().unwrap_or(0)));
+            }
+            None => cursor.position = position,
+        }
+
+        let mut post = None;
+        let position = cursor.position;
+        if cursor.remainder().starts_with('-') {
+            cursor.position += 1;
+            post = cursor.take_number();
+        }
+        if post.is_none() {
+            cursor.position = position;
+            cursor.skip_separator();
+            match cursor.take_prefix(&["post", "rev", "r"]) {
+                Some(_) => post = Some(cursor.take_separated_number().unwrap_or(0)),
+                None => cursor.position = position,
+            }
+        }
+
+        let mut dev = None;
+        let position = cursor.position;
+        cursor.skip_separator();
+        match cursor.take_prefix(&["dev"]) {
+            Some(_) => dev = Some(cursor.take_separated_number().unwrap_or(0)),
+            None => cursor.position = position,
+        }
+
+        let mut local = Vec::new();
+        if cursor.remainder().starts_with('+') {
+            let label = &cursor.remainder()[1..];
+            for segment in label.split(|c| c == '.' || c == '-' || c == '_') {
+                if segment.is_empty() || !segment.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(error());
+                }
+                local.push(match segment.parse() {
+                    Ok(v) => LocalSegment::Number(v),
+                    Err(_) => LocalSegment::String(segment.to_string()),
+                });
+            }
+            cursor.position = cursor.value.len();
+        }
+
+        if !cursor.is_done() {
+            return Err(error());
+        }
+        Ok(Self {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            local,
+        })
+    }
+
+    /// Returns true for pre-release and development release versions.
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Returns the version without its local version label.
+    pub fn public(&self) -> Self {
+        Self {
+            local: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the release segment component at the given index, or zero if not present.
+    pub fn release_component(&self, index: usize) -> u64 {
+        self.release.get(index).cloned().unwrap_or(0)
+    }
+
+    /// Compare release segments, ignoring trailing zeros.
+    fn cmp_release(&self, other: &Self) -> Ordering {
+        let length = self.release.len().max(other.release.len());
+        (0..length)
+            .map(|index| {
+                self.release_component(index)
+                    .cmp(&other.release_component(index))
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl std::fmt::Display for Version {
+    /// Writes the normalized version string.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|v| v.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((phase, number)) = &self.pre {
+            let phase = match phase {
+                PreRelease::Alpha => "a",
+                PreRelease::Beta => "b",
+                PreRelease::ReleaseCandidate => "rc",
+            };
+            write!(f, "{}{}", phase, number)?;
+        }
+        if let Some(post) = self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        if !self.local.is_empty() {
+            let local: Vec<String> = self
+                .local
+                .iter()
+                .map(|segment| match segment {
+                    LocalSegment::Number(v) => v.to_string(),
+                    LocalSegment::String(v) => v.clone(),
+                })
+                .collect();
+            write!(f, "+{}", local.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Development releases without a pre-release phase sort before pre-releases. Final
+        // releases sort after pre-releases.
+        let pre_key = |version: &Self| match (&version.pre, &version.post, &version.dev) {
+            (None, None, Some(_)) => (0, None),
+            (Some(pre), _, _) => (1, Some(*pre)),
+            _ => (2, None),
+        };
+        let post_key = |version: &Self| version.post.map_or((0, 0), |v| (1, v));
+        let dev_key = |version: &Self| version.dev.map_or((1, 0), |v| (0, v));
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| self.cmp_release(other))
+            .then_with(|| pre_key(self).cmp(&pre_key(other)))
+            .then_with(|| post_key(self).cmp(&post_key(other)))
+            .then_with(|| dev_key(self).cmp(&dev_key(other)))
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+/// Version specifier comparison operator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operator {
+    Compatible,
+    Equal,
+    NotEqual,
+    LessEqual,
+    GreaterEqual,
+    Less,
+    Greater,
+    Arbitrary,
+}
+
+/// PEP 440 version specifier (e.g. `>=1.2` or `==1.4.*`).
+#[derive(Debug, Clone)]
+pub struct Specifier {
+    operator: Operator,
+    version: Version,
+    version_string: String,
+    is_wildcard: bool,
+}
+
+impl Specifier {
+    pub fn parse(specifier: &str) -> Result<Self> {
+        let specifier: String = specifier.chars().filter(|c| !c.is_whitespace()).collect();
+        let operators = vec![
+            ("===", Operator::Arbitrary),
+            ("~=", Operator::Compatible),
+            ("==", Operator::Equal),
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessEqual),
+            (">=", Operator::GreaterEqual),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+        ];
+        let (version_string, operator) = operators
+            .into_iter()
+            .find_map(|(prefix, operator)| specifier.strip_prefix(prefix).map(|v| (v, operator)))
+            .ok_or(format_err!(
+                "Failed to parse version specifier: {}",
+                specifier
+            ))?;
+
+        let (version, is_wildcard) = match version_string.strip_suffix(".*") {
+            Some(v) if operator == Operator::Equal || operator == Operator::NotEqual => (v, true),
+            _ => (version_string, false),
+        };
+        let version = match operator {
+            // Arbitrary equality compares strings. The version need not be valid.
+            Operator::Arbitrary => Version::parse(&version).unwrap_or(Version {
+                epoch: 0,
+                release: vec![0],
+                pre: None,
+                post: None,
+                dev: None,
+                local: Vec::new(),
+            }),
+            _ => Version::parse(&version)?,
+        };
+        if operator == Operator::Compatible && version.release.len() < 2 {
+            return Err(format_err!(
+                "Compatible release specifier requires at least two release segments: {}",
+                specifier
+            ));
+        }
+        Ok(Self {
+            operator,
+            version,
+            version_string: version_string.to_string(),
+            is_wildcard,
+        })
+    }
+
+    /// Returns true if the specifier version is a pre-release.
+    pub fn is_prerelease(&self) -> bool {
+        self.version.is_prerelease()
+    }
+
+    /// Returns true if the version matches the prefix of the specifier version.
+    fn matches_prefix(&self, version: &Version, prefix_length: usize) -> bool {
+        version.epoch == self.version.epoch
+            && (0..prefix_length).all(|index| {
+                version.release_component(index) == self.version.release_component(index)
+            })
+    }
+
+    fn matches_equal(&self, version: &Version) -> bool {
+        if self.is_wildcard {
+            return self.matches_prefix(&version, self.version.release.len());
+        }
+        if self.version.local.is_empty() {
+            version.public() == self.version
+        } else {
+            *version == self.version
+        }
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        let public_version = version.public();
+        match self.operator {
+            Operator::Arbitrary => version.to_string() == self.version_string.to_lowercase(),
+            Operator::Equal => self.matches_equal(&version),
+            Operator::NotEqual => !self.matches_equal(&version),
+            Operator::Compatible => {
+                public_version >= self.version
+                    && self.matches_prefix(&version, self.version.release.len() - 1)
+            }
+            Operator::LessEqual => public_version <= self.version,
+            Operator::GreaterEqual => public_version >= self.version,
+            // Exclusive comparisons exclude pre-releases and post-releases of the specifier
+            // version unless the specifier version is itself a pre-release or post-release.
+            Operator::Less => {
+                public_version < self.version
+                    && (self.version.is_prerelease()
+                        || !version.is_prerelease()
+                        || version.cmp_release(&self.version) != Ordering::Equal)
+            }
+            Operator::Greater => {
+                public_version > self.version
+                    && (self.version.post.is_some()
+                        || version.post.is_none()
+                        || version.cmp_release(&self.version) != Ordering::Equal)
+            }
+        }
+    }
+}
+
+/// Comma separated set of version specifiers. An empty set contains all versions.
+#[derive(Debug, Clone, Default)]
+pub struct SpecifierSet {
+    specifiers: Vec<Specifier>,
+}
+
+impl SpecifierSet {
+    pub fn parse(specifiers: &str) -> Result<Self> {
+        Ok(Self {
+            specifiers: specifiers
+                .split(',')
+                .filter(|specifier| !specifier.trim().is_empty())
+                .map(|specifier| Specifier::parse(&specifier))
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    /// Returns true if any specifier explicitly refers to a pre-release.
+    pub fn is_prerelease(&self) -> bool {
+        self.specifiers
+            .iter()
+            .any(|specifier| specifier.is_prerelease())
+    }
+
+    /// Returns true if the version satisfies all specifiers.
+    ///
+    /// Pre-releases are only contained if `prereleases` is set or if a specifier explicitly
+    /// refers to a pre-release.
+    pub fn contains(&self, version: &Version, prereleases: bool) -> bool {
+        if version.is_prerelease() && !prereleases && !self.is_prerelease() {
+            return false;
+        }
+        self.specifiers
+            .iter()
+            .all(|specifier| specifier.contains(&version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(&version).unwrap()
+    }
+
+    fn contains(specifier: &str, version_string: &str) -> bool {
+        Specifier::parse(&specifier)
+            .unwrap()
+            .contains(&version(&version_string))
+    }
+
+    #[test]
+    fn version_ordering() {
+        let ordered_versions = vec![
+            "1.0.dev1",
+            "1.0a1.dev1",
+            "1.0a1",
+            "1.0a1.post1",
+            "1.0a2",
+            "1.0b1",
+            "1.0rc1",
+            "1.0",
+            "1.0+abc",
+            "1.0+abc.1",
+            "1.0+1",
+            "1.0.post1.dev1",
+            "1.0.post1",
+            "1.1.dev1",
+            "1.1",
+            "1!0.1",
+        ];
+        for pair in ordered_versions.windows(2) {
+            assert!(
+                version(&pair[0]) < version(&pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn version_normalization() {
+        assert_eq!(version("1.0"), version("1.0.0"));
+        assert_eq!(version("v1.0-RC.1").to_string(), "1.0rc1");
+        assert_eq!(version("1.0alpha").to_string(), "1.0a0");
+        assert_eq!(version("1.0-1").to_string(), "1.0.post1");
+        assert_eq!(version("1.0.r2.dev3").to_string(), "1.0.post2.dev3");
+        assert_eq!(version("1.0+Ubuntu-1").to_string(), "1.0+ubuntu.1");
+        assert!(Version::parse("not a version").is_err());
+        assert!(Version::parse("1.0+").is_err());
+    }
+
+    #[test]
+    fn version_is_prerelease() {
+        assert!(version("1.0rc1").is_prerelease());
+        assert!(version("1.0.dev1").is_prerelease());
+        assert!(version("1.0.post1.dev1").is_prerelease());
+        assert!(!version("1.0.post1").is_prerelease());
+        assert!(!version("1.0+local").is_prerelease());
+    }
+
+    #[test]
+    fn exclusive_comparisons() {
+        assert!(contains("<1.0", "0.9"));
+        assert!(!contains("<1.0", "1.0rc1"));
+        assert!(!contains("<1.0", "1.0.dev1"));
+        assert!(contains("<1.0rc2", "1.0rc1"));
+        assert!(contains(">1.0", "1.1"));
+        assert!(!contains(">1.0", "1.0.post1"));
+        assert!(!contains(">1.0", "1.0+local"));
+        assert!(contains(">1.0.post1", "1.0.post2"));
+        assert!(contains("<=1.0", "1.0+local"));
+        assert!(contains(">=1.0", "1.0.post1"));
+    }
+
+    #[test]
+    fn compatible_release() {
+        assert!(contains("~=1.4.5", "1.4.5"));
+        assert!(contains("~=1.4.5", "1.4.9"));
+        assert!(!contains("~=1.4.5", "1.5.0"));
+        assert!(!contains("~=1.4.5", "1.4.4"));
+        assert!(contains("~=2.2", "2.9"));
+        assert!(!contains("~=2.2", "3.0"));
+        assert!(Specifier::parse("~=1").is_err());
+    }
+
+    #[test]
+    fn equality() {
+        assert!(contains("==1.4.*", "1.4"));
+        assert!(contains("==1.4.*", "1.4.5.post1"));
+        assert!(!contains("==1.4.*", "1.5"));
+        assert!(!contains("!=1.4.*", "1.4.2"));
+        assert!(contains("!=1.4.*", "1.5"));
+        assert!(contains("==1.0", "1.0.0"));
+        assert!(contains("==1.0", "1.0+local"));
+        assert!(!contains("==1.0+local", "1.0"));
+        assert!(contains("===1.0", "1.0"));
+        assert!(!contains("===1.0", "1.0.0"));
+    }
+
+    #[test]
+    fn specifier_set_prereleases() {
+        let specifiers = SpecifierSet::parse(">=1.0, <2.0").unwrap();
+        assert!(specifiers.contains(&version("1.5"), false));
+        assert!(!specifiers.contains(&version("2.0"), false));
+        assert!(!specifiers.contains(&version("1.5b1"), false));
+        assert!(specifiers.contains(&version("1.5b1"), true));
+
+        let specifiers = SpecifierSet::parse(">=2.0b1").unwrap();
+        assert!(specifiers.contains(&version("2.0b2"), false));
+        assert!(SpecifierSet::parse("")
+            .unwrap()
+            .contains(&version("3"), false));
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::Result;
+
+use crate::{environment, requirements, version_error};
+
+/// `pip install` options which take a value.
+static OPTIONS_WITH_VALUE: &[&str] = &[
+    "-r",
+    "--requirement",
+    "-c",
+    "--constraint",
+    "-e",
+    "--editable",
+    "-i",
+    "--index-url",
+    "--extra-index-url",
+    "-f",
+    "--find-links",
+    "-t",
+    "--target",
+    "--prefix",
+    "--root",
+    "--src",
+    "--trusted-host",
+    "--platform",
+    "--python-version",
+    "--implementation",
+    "--abi",
+    "--no-binary",
+    "--only-binary",
+    "--upgrade-strategy",
+    "--progress-bar",
+    "-C",
+    "--config-settings",
+    "--global-option",
+    "--log",
+    "--cache-dir",
+    "--proxy",
+    "--retries",
+    "--timeout",
+    "--exists-action",
+    "--cert",
+    "--client-cert",
+    "--report",
+];
+
+/// Parsed `pip install` command.
+#[derive(Debug, Clone, Default)]
+pub struct InstallCommand {
+    pub requirement_strings: Vec<String>,
+    pub requirements_files: Vec<String>,
+    pub index_url: Option<String>,
+}
+
+/// Split shell command line into commands of words.
+///
+/// Handles single and double quotes and backslash escapes. Commands are separated by unquoted
+/// `&&`, `||`, `;`, `|` and newlines.
+pub fn split_commands(command_line: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    let mut chars = command_line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            } else if c == '\\' && q == '"' {
+                if let Some(next) = chars.next() {
+                    word.get_or_insert_with(String::new).push(next);
+                }
+            } else {
+                word.get_or_insert_with(String::new).push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '"' => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    if next != '\n' {
+                        word.get_or_insert_with(String::new).push(next);
+                    }
+                }
+            }
+            '&' | '|' | ';' | '\n' => {
+                words.extend(word.take());
+                if !words.is_empty() {
+                    commands.push(words);
+                    words = Vec::new();
+                }
+            }
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.take());
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    commands
+}
+
+/// Returns the index of the first argument following `pip install` within the command.
+///
+/// Recognises `pip`, `pip3`, `pip3.X` (optionally with a path), `python -m pip` and
+/// `uv pip` invocations.
+fn find_install_arguments(words: &Vec<String>) -> Option<usize> {
+    for (index, word) in words.iter().enumerate() {
+        let program = word.rsplit('/').next().unwrap_or(word);
+        let is_pip = program == "pip"
+            || program
+                .strip_prefix("pip3")
+                .map_or(false, |v| v.is_empty() || v.starts_with('.'));
+        if is_pip && words.get(index + 1).map(|w| w.as_str()) == Some("install") {
+            return Some(index + 2);
+        }
+    }
+    None
+}
+
+/// Parse `pip install` arguments, excluding the `pip install` command itself.
+pub fn parse_install_arguments(arguments: &[String]) -> InstallCommand {
+    let mut install_command = InstallCommand::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if !argument.starts_with('-') {
+            install_command.requirement_strings.push(argument.clone());
+            continue;
+        }
+
+        let (option, inline_value) = match argument.find('=') {
+            Some(index) if argument.starts_with("--") => {
+                (&argument[..index], Some(argument[index + 1..].to_string()))
+            }
+            _ => (argument.as_str(), None),
+        };
+        // Short options may be joined with their value, e.g. `-rrequirements.txt`.
+        let short_option_end = option.char_indices().nth(2).map(|(index, _)| index);
+        let (option, inline_value) = match (inline_value, short_option_end) {
+            (None, Some(index)) if !option.starts_with("--") => {
+                (&option[..index], Some(option[index..].to_string()))
+            }
+            (inline_value, _) => (option, inline_value),
+        };
+        if !OPTIONS_WITH_VALUE.contains(&option) {
+            continue;
+        }
+        let value = match inline_value {
+            Some(v) => v,
+            None => match arguments.next() {
+                Some(v) => v.clone(),
+                None => break,
+            },
+        };
+        match option {
+            "-r" | "--requirement" => install_command.requirements_files.push(value),
+            // Editable installs are local paths or VCS URLs.
+            "-e" | "--editable" => install_command.requirement_strings.push(value),
+            "-i" | "--index-url" => install_command.index_url = Some(value),
+            _ => {}
+        }
+    }
+    install_command
+}
+
+/// Parse `pip install` commands from a shell command line.
+pub fn parse_install_commands(command_line: &str) -> Vec<InstallCommand> {
+    let mut install_commands = Vec::new();
+    for words in split_commands(&command_line) {
+        let start = match find_install_arguments(&words) {
+            Some(v) => v,
+            None => continue,
+        };
+        install_commands.push(parse_install_arguments(&words[start..]));
+    }
+    install_commands
+}
+
+/// Convert `pip install` requirement argument into a dependency.
+///
+/// Returns the registry host name of the dependency and the dependency. Packages are attributed
+/// to the given package index host name, URL and path arguments to their source host. Returns
+/// None for requirements which do not apply within the target environment. Arguments which
+/// reference shell variables can not be resolved statically and are reported with a version
+/// error. Arguments which can not be parsed because of shell variables (e.g. `$PACKAGES`) are
+/// reported as a dependency named by the argument.
+pub fn get_dependency(
+    requirement_string: &str,
+    index_host_name: &str,
+    environment: &environment::Environment,
+) -> Result<Option<(String, vouch_lib::extension::Dependency)>> {
+    let requirement = match requirements::parse_requirement_line(&requirement_string) {
+        Ok(Some(v)) => v,
+        Ok(None) => return Ok(None),
+        Err(error) => {
+            if requirement_string.contains('$') {
+                return Ok(Some((
+                    index_host_name.to_string(),
+                    vouch_lib::extension::Dependency {
+                        name: requirement_string.to_string(),
+                        version: Err(
+                            vouch_lib::extension::common::VersionError::from_parse_error(
+                                version_error::SHELL_VARIABLE_ERROR,
+                            ),
+                        ),
+                    },
+                )));
+            }
+            return Err(error);
+        }
+    };
+    if !requirements::applies(&requirement, &environment)? {
+        return Ok(None);
+    }
+
+    let mut dependency = requirements::to_dependency(&requirement);
+    if requirement_string.contains('$') {
+        dependency.version = Err(
+            vouch_lib::extension::common::VersionError::from_parse_error(&requirement.specifier),
+        );
+    }
+    Ok(Some((
+        requirements::get_host_name(&requirement, &index_host_name),
+        dependency,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> InstallCommand {
+        let arguments: Vec<String> = arguments.iter().map(|v| v.to_string()).collect();
+        parse_install_arguments(&arguments)
+    }
+
+    #[test]
+    fn joined_short_options() {
+        let install_command = parse(&["-rrequirements.txt", "-i", "https://example.com/simple"]);
+        assert_eq!(install_command.requirements_files, vec!["requirements.txt"]);
+        assert_eq!(
+            install_command.index_url,
+            Some("https://example.com/simple".to_string())
+        );
+    }
+
+    #[test]
+    fn multibyte_options() {
+        let install_command = parse(&["-é", "-éx", "--é=1", "requests"]);
+        assert_eq!(install_command.requirement_strings, vec!["requests"]);
+        assert!(install_command.requirements_files.is_empty());
+    }
+}
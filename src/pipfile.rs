@@ -1,7 +1,24 @@
 use anyhow::{format_err, Context, Result};
-use std::collections::HashSet;
+use sha2::Digest;
+use std::collections::{BTreeMap, BTreeSet};
 
-static HOST_NAME: &str = "pypi.org";
+use crate::{arguments, environment, location, marker, source, version_error, warning};
+
+static VCS_KEYS: &[&str] = &["git", "hg", "svn", "bzr"];
+static DEFAULT_SOURCE_URL: &str = "https://pypi.org/simple";
+
+/// Pipfile tables which are not included in the Pipfile hash as package categories.
+static NON_CATEGORY_TABLES: &[&str] = &[
+    "source",
+    "packages",
+    "dev-packages",
+    "requires",
+    "scripts",
+    "pipfile",
+    "pipenv",
+    "default",
+    "develop",
+];
 
 /// Parse and clean package version string.
 ///
@@ -21,46 +38,453 @@ fn get_parsed_version(version: &Option<&str>) -> vouch_lib::extension::common::V
     Ok(cleaned_version.to_string())
 }
 
+/// Package index name and its registry host name.
+#[derive(Debug, Clone)]
+struct Source {
+    name: String,
+    host_name: String,
+}
+
+/// Parse package index sources from the lock file `_meta.sources` or Pipfile `source` array.
+fn get_sources(json_sources: &serde_json::Value) -> Result<Vec<Source>> {
+    let json_sources = match json_sources.as_array() {
+        Some(v) => v,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut sources = Vec::new();
+    for json_source in json_sources {
+        let name = json_source["name"]
+            .as_str()
+            .ok_or(format_err!("Failed to parse source name."))?;
+        let url = json_source["url"]
+            .as_str()
+            .ok_or(format_err!("Failed to parse URL of source: {}", name))?;
+        sources.push(Source {
+            name: name.to_string(),
+            host_name: source::get_index_host_name(&url)
+                .context(format!("Failed to parse URL of source: {}", name))?,
+        });
+    }
+    Ok(sources)
+}
+
+/// Returns VCS repository URL of the package entry, if sourced from version control.
+fn get_vcs_url(entry: &serde_json::Value) -> Option<&str> {
+    VCS_KEYS.iter().find_map(|key| entry[key].as_str())
+}
+
+/// Returns true if the package entry is sourced from a local path, local file or installed as
+/// editable without a VCS source.
+fn is_local_entry(entry: &serde_json::Value) -> bool {
+    if get_vcs_url(&entry).is_some() {
+        return false;
+    }
+    match entry["file"].as_str() {
+        Some(file_url) => file_url.starts_with("file:"),
+        None => entry["path"].is_string() || entry["editable"].as_bool() == Some(true),
+    }
+}
+
+/// Returns registry host name of the package entry.
+///
+/// VCS packages use the repository host name. Packages sourced from a local path, local file or
+/// installed as editable without a VCS source use the local host name. Other packages use the
+/// host of their named `index` source, or of the first source if no index is given.
+fn get_host_name(entry: &serde_json::Value, sources: &Vec<Source>) -> Result<String> {
+    if let Some(vcs_url) = get_vcs_url(&entry) {
+        return source::get_host_name(&vcs_url);
+    }
+    if is_local_entry(&entry) {
+        return Ok(source::LOCAL_HOST_NAME.to_string());
+    }
+    if let Some(file_url) = entry["file"].as_str() {
+        return source::get_host_name(&file_url);
+    }
+
+    match entry["index"].as_str() {
+        Some(index) => sources
+            .iter()
+            .find(|source| source.name == index)
+            .map(|source| source.host_name.clone())
+            .ok_or(format_err!(
+                "Failed to find package index source: {}",
+                index
+            )),
+        None => Ok(sources
+            .first()
+            .map(|source| source.host_name.clone())
+            .unwrap_or(source::PYPI_HOST_NAME.to_string())),
+    }
+}
+
+/// Parse package entry version.
+///
+/// VCS packages are versioned by their commit reference. Local packages are reported with an
+/// error which notes that they can not be reviewed from a registry. Pipfile entries may be given
+/// as a version specifier string.
+fn get_entry_version(
+    entry: &serde_json::Value,
+) -> vouch_lib::extension::common::VersionParseResult {
+    if get_vcs_url(&entry).is_some() {
+        return match entry["ref"].as_str() {
+            Some(v) if !v.is_empty() => Ok(v.to_string()),
+            _ => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+        };
+    }
+    if is_local_entry(&entry) {
+        return Err(
+            vouch_lib::extension::common::VersionError::from_parse_error(
+                version_error::LOCAL_SOURCE_ERROR,
+            ),
+        );
+    }
+    get_parsed_version(&entry.as_str().or(entry["version"].as_str()))
+}
+
+/// Returns the environment marker of the package entry, if any.
+///
+/// Combines the `markers` key with marker variable keys (e.g. `sys_platform = "== 'win32'"`).
+fn get_entry_marker(entry: &serde_json::Value) -> Option<String> {
+    let mut markers = Vec::new();
+    if let Some(marker) = entry["markers"].as_str() {
+        markers.push(format!("({})", marker));
+    }
+    for variable in marker::VARIABLES {
+        if let Some(value) = entry[variable].as_str() {
+            markers.push(format!("{} {}", variable, value));
+        }
+    }
+    if markers.is_empty() {
+        return None;
+    }
+    Some(markers.join(" and "))
+}
+
+/// Parse section packages, locating each with the given function of the package name.
+///
+/// Packages which do not apply within the target environment are skipped.
 fn parse_section(
     json_section: &serde_json::map::Map<std::string::String, serde_json::value::Value>,
-) -> Result<HashSet<vouch_lib::extension::Dependency>> {
-    let mut dependencies = HashSet::new();
+    sources: &Vec<Source>,
+    environment: &environment::Environment,
+    get_location: impl Fn(&str) -> location::Location,
+) -> Result<BTreeMap<String, location::Dependencies>> {
+    let mut all_dependencies = BTreeMap::new();
     for (package_name, entry) in json_section {
-        let version_parse_result = get_parsed_version(&entry["version"].as_str());
+        if let Some(entry_marker) = get_entry_marker(&entry) {
+            if !marker::evaluate(&entry_marker, &environment, &BTreeSet::new()).context(format!(
+                "Failed to evaluate markers of package: {}",
+                package_name
+            ))? {
+                continue;
+            }
+        }
+        let host_name = get_host_name(&entry, &sources).context(format!(
+            "Failed to parse source of package: {}",
+            package_name
+        ))?;
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: package_name.clone(),
+                    version: get_entry_version(&entry),
+                },
+                get_location(&package_name),
+            );
+    }
+    Ok(all_dependencies)
+}
 
-        dependencies.insert(vouch_lib::extension::Dependency {
-            name: package_name.clone(),
-            version: version_parse_result,
-        });
+/// Write JSON string with non-ASCII characters escaped, as Python's `json.dumps` does by default.
+fn write_json_string(value: &str, output: &mut String) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            c if (c as u32) < 0x20 || !c.is_ascii() => {
+                let mut buffer = [0; 2];
+                for unit in c.encode_utf16(&mut buffer) {
+                    output.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => output.push(c),
+        }
     }
-    Ok(dependencies)
+    output.push('"');
+}
+
+/// Write TOML value as compact JSON with sorted keys.
+fn write_json(value: &toml::Value, output: &mut String) {
+    match value {
+        toml::Value::String(v) => write_json_string(&v, output),
+        toml::Value::Integer(v) => output.push_str(&v.to_string()),
+        toml::Value::Float(v) => output.push_str(&format!("{:?}", v)),
+        toml::Value::Boolean(v) => output.push_str(&v.to_string()),
+        toml::Value::Datetime(v) => write_json_string(&v.to_string(), output),
+        toml::Value::Array(values) => {
+            output.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_json(&value, output);
+            }
+            output.push(']');
+        }
+        toml::Value::Table(table) => {
+            let mut keys: Vec<&String> = table.keys().collect();
+            keys.sort();
+            output.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_json_string(&key, output);
+                output.push(':');
+                write_json(&table[key], output);
+            }
+            output.push('}');
+        }
+    }
+}
+
+/// Returns the Pipfile sha256 hash as computed by pipenv.
+///
+/// The Pipfile is restructured into the lock file layout and serialised as compact JSON with
+/// sorted keys before hashing.
+fn get_pipfile_hash(file_path: &std::path::PathBuf) -> Result<String> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let pipfile: toml::value::Table = toml::from_str(&content)
+        .context(format!("Failed to parse Pipfile: {}", file_path.display()))?;
+    let get_table = |key: &str| {
+        pipfile
+            .get(key)
+            .cloned()
+            .unwrap_or(toml::Value::Table(toml::value::Table::new()))
+    };
+
+    let sources = match pipfile.get("source") {
+        Some(v) => v.clone(),
+        None => {
+            let mut default_source = toml::value::Table::new();
+            default_source.insert("name".to_string(), toml::Value::from("pypi"));
+            default_source.insert("url".to_string(), toml::Value::from(DEFAULT_SOURCE_URL));
+            default_source.insert("verify_ssl".to_string(), toml::Value::from(true));
+            toml::Value::Array(vec![toml::Value::Table(default_source)])
+        }
+    };
+    let mut meta = toml::value::Table::new();
+    meta.insert("sources".to_string(), sources);
+    meta.insert("requires".to_string(), get_table("requires"));
+
+    let mut data = toml::value::Table::new();
+    data.insert("_meta".to_string(), toml::Value::Table(meta));
+    data.insert("default".to_string(), get_table("packages"));
+    data.insert("develop".to_string(), get_table("dev-packages"));
+    // Custom package categories.
+    for (key, value) in &pipfile {
+        if !NON_CATEGORY_TABLES.contains(&key.as_str()) {
+            data.insert(key.clone(), value.clone());
+        }
+    }
+
+    let mut json = String::new();
+    write_json(&toml::Value::Table(data), &mut json);
+    Ok(format!("{:x}", sha2::Sha256::digest(json.as_bytes())))
+}
+
+/// Check that the lock file hash matches its sibling Pipfile, if present.
+///
+/// A stale lock file is reported as a warning, or as an error if `error_on_stale_lock` is set.
+fn check_lock_hash(
+    file_path: &std::path::PathBuf,
+    pipfile_lock: &serde_json::Value,
+    arguments: &arguments::Arguments,
+) -> Result<()> {
+    let pipfile_path = file_path.with_file_name("Pipfile");
+    if !pipfile_path.is_file() {
+        return Ok(());
+    }
+    let pipfile_hash = get_pipfile_hash(&pipfile_path)?;
+    if pipfile_lock["_meta"]["hash"]["sha256"].as_str() == Some(pipfile_hash.as_str()) {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Pipfile.lock is out of date with Pipfile (hash mismatch): {}",
+        file_path.display()
+    );
+    if arguments.error_on_stale_lock {
+        return Err(format_err!(message));
+    }
+    warning::print(&message, &arguments);
+    Ok(())
 }
 
 /// Parse dependencies from project dependencies definition file.
-pub fn get_dependencies(
+///
+/// Returns one file defined dependencies structure per section and registry host, labelled with
+/// the section name (e.g. `Pipfile.lock[develop]`). Registry host names are resolved through
+/// `_meta.sources`. Packages sourced from local paths are grouped under the local host name.
+/// The `develop` section is skipped if `production_only` is set. Packages whose markers do not
+/// hold within the target environment are skipped. The lock file is checked against the Pipfile
+/// hash.
+pub fn get_file_defined_dependencies(
     file_path: &std::path::PathBuf,
-) -> Result<HashSet<vouch_lib::extension::Dependency>> {
-    let file = std::fs::File::open(file_path)?;
-    let reader = std::io::BufReader::new(file);
-    let pipfile: serde_json::Value = serde_json::from_reader(reader).context(format!(
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let pipfile: serde_json::Value = serde_json::from_str(&content).context(format!(
         "Failed to parse Pipfile.lock: {}",
         file_path.display()
     ))?;
 
-    let mut all_dependencies: HashSet<vouch_lib::extension::Dependency> = HashSet::new();
+    check_lock_hash(&file_path, &pipfile, &arguments)?;
+
+    let sources = get_sources(&pipfile["_meta"]["sources"]).context(format!(
+        "Failed to parse '_meta.sources' of Pipfile.lock: {}",
+        file_path.display()
+    ))?;
+
+    let mut all_file_defined_dependencies = Vec::new();
     for section in vec!["default", "develop"] {
+        if arguments.production_only && section == "develop" {
+            continue;
+        }
         let json_section = pipfile[section].as_object().ok_or(format_err!(
             "Failed to parse '{}' section of Pipfile.lock",
             section
         ))?;
-        let dependencies = parse_section(&json_section)?;
-        for dependency in dependencies {
-            all_dependencies.insert(dependency);
+        let section_pattern = format!("\"{}\"", section);
+        let get_location = |package_name: &str| {
+            location::Location::find(
+                &file_path,
+                &content,
+                &[&section_pattern, &format!("\"{}\"", package_name)],
+            )
+            .with_key_path(&format!("{}.{}", section, package_name))
+        };
+        let all_dependencies = parse_section(&json_section, &sources, &environment, get_location)
+            .context(format!(
+            "Failed to parse '{}' section of Pipfile.lock: {}",
+            section,
+            file_path.display()
+        ))?;
+        all_file_defined_dependencies.extend(location::from_registry_hosts(
+            &std::path::PathBuf::from(format!("{}[{}]", file_path.display(), section)),
+            all_dependencies,
+        ));
+    }
+    Ok(all_file_defined_dependencies)
+}
+
+/// Parse dependencies from Pipfile. Used if the Pipfile has not been locked.
+///
+/// Returns one file defined dependencies structure per section and registry host, labelled with
+/// the section name (e.g. `Pipfile[dev-packages]`). Only exact `==` pins are reported as
+/// versions, wildcard and range specifiers are reported with a version error. The
+/// `dev-packages` section is skipped if `production_only` is set. Packages whose markers do not
+/// hold within the target environment are skipped.
+pub fn get_pipfile_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let pipfile: toml::Value = toml::from_str(&content)
+        .context(format!("Failed to parse Pipfile: {}", file_path.display()))?;
+    let pipfile = serde_json::to_value(&pipfile)?;
+
+    let sources = get_sources(&pipfile["source"]).context(format!(
+        "Failed to parse 'source' of Pipfile: {}",
+        file_path.display()
+    ))?;
+
+    let mut all_file_defined_dependencies = Vec::new();
+    for section in vec!["packages", "dev-packages"] {
+        if arguments.production_only && section == "dev-packages" {
+            continue;
         }
+        let json_section = match pipfile[section].as_object() {
+            Some(v) => v,
+            None => continue,
+        };
+        let section_pattern = format!("[{}]", section);
+        let get_location = |package_name: &str| {
+            location::Location::find(&file_path, &content, &[&section_pattern, package_name])
+                .with_key_path(&format!("{}.{}", section, package_name))
+        };
+        let all_dependencies = parse_section(&json_section, &sources, &environment, get_location)
+            .context(format!(
+            "Failed to parse '{}' section of Pipfile: {}",
+            section,
+            file_path.display()
+        ))?;
+        all_file_defined_dependencies.extend(location::from_registry_hosts(
+            &std::path::PathBuf::from(format!("{}[{}]", file_path.display(), section)),
+            all_dependencies,
+        ));
     }
-    Ok(all_dependencies)
+    Ok(all_file_defined_dependencies)
+}
+
+/// Returns the Pipfile within the given directory if it has not been locked.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let file_path = directory.join("Pipfile");
+    if !file_path.is_file() || directory.join("Pipfile.lock").is_file() {
+        return Vec::new();
+    }
+    vec![file_path]
 }
 
-pub fn get_registry_host_name() -> String {
-    HOST_NAME.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipfile_hash() {
+        // Pipfile of the python-rsa project, and the hash which pipenv recorded for it within
+        // the project's Pipfile.lock.
+        let directory = std::env::temp_dir().join("vouch-py-pipfile-hash");
+        std::fs::create_dir_all(&directory).unwrap();
+        let file_path = directory.join("Pipfile");
+        std::fs::write(
+            &file_path,
+            r#"[[source]]
+url = "https://pypi.org/simple"
+verify_ssl = true
+name = "pypi"
+
+[packages]
+"pyasn1" = ">=0.1.3"
+
+[dev-packages]
+tox = "*"
+mock = ">=2.0.0"
+Sphinx = "*"
+coveralls = "*"
+pytest = "*"
+pytest-cov = "*"
+pathlib2 = {version = "*", markers="python_version < '3.6'"}
+
+[requires]
+python_version = "3.6"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            get_pipfile_hash(&file_path).unwrap(),
+            "b97ee8eb9c129d192e7ce44ecb3ffb3aee1097dd3dc15c1ad10fad6b686a7705"
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{arguments, environment, location, marker, source};
+
+/// Returns registry host name for package source.
+///
+/// Packages without a source table are hosted on PyPI.
+fn get_source_host_name(package: &toml::Value) -> Result<String> {
+    let source = match package.get("source") {
+        Some(v) => v,
+        None => return Ok(source::PYPI_HOST_NAME.to_string()),
+    };
+    let source_type = source.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    if source_type == "directory" || source_type == "file" {
+        return Ok(source::LOCAL_HOST_NAME.to_string());
+    }
+
+    let source_url = source
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or(format_err!("Failed to parse package source URL."))?;
+    source::get_index_host_name(&source_url)
+}
+
+/// Parse package version.
+///
+/// Git sources are pinned by their resolved commit rather than the package version.
+fn get_parsed_version(package: &toml::Value) -> vouch_lib::extension::common::VersionParseResult {
+    let source = package.get("source");
+    let source_type = source
+        .and_then(|source| source.get("type"))
+        .and_then(|v| v.as_str());
+    if source_type == Some("git") {
+        let resolved_reference = source
+            .and_then(|source| source.get("resolved_reference"))
+            .and_then(|v| v.as_str());
+        return match resolved_reference {
+            Some(v) => Ok(v.to_string()),
+            None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+        };
+    }
+
+    match package.get("version").and_then(|v| v.as_str()) {
+        Some(v) if !v.is_empty() => Ok(v.to_string()),
+        _ => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+    }
+}
+
+/// Returns true if the package is only required for development.
+///
+/// Lock file version 1.x packages are given a `category`. Later versions list the dependency
+/// `groups` which require the package.
+fn is_development_package(package: &toml::Value) -> bool {
+    if let Some(category) = package.get("category").and_then(|v| v.as_str()) {
+        return category == "dev";
+    }
+    match package.get("groups").and_then(|v| v.as_array()) {
+        Some(groups) => !groups.iter().any(|group| group.as_str() == Some("main")),
+        None => false,
+    }
+}
+
+/// Returns the environment markers of the package.
+///
+/// Markers are given either as a single string or as a table of markers per dependency group.
+fn get_markers(package: &toml::Value) -> Vec<&str> {
+    match package.get("markers") {
+        Some(toml::Value::String(v)) => vec![v.as_str()],
+        Some(toml::Value::Table(table)) => table.values().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Returns one file defined dependencies structure per registry host. Supports lock file versions 1.x and 2.x,
+/// both of which list packages as `[[package]]` tables. Packages whose `markers` do not hold
+/// within the target environment are skipped. Development packages are skipped if
+/// `production_only` is set.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let lock: toml::Value = toml::from_str(&content).context(format!(
+        "Failed to parse poetry.lock: {}",
+        file_path.display()
+    ))?;
+
+    let lock_version = lock
+        .get("metadata")
+        .and_then(|metadata| metadata.get("lock-version"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("1.0");
+    if !(lock_version.starts_with("1.") || lock_version.starts_with("2.")) {
+        return Err(format_err!(
+            "Unsupported poetry.lock lock-version '{}': {}",
+            lock_version,
+            file_path.display()
+        ));
+    }
+
+    let packages = match lock.get("package") {
+        Some(v) => v
+            .as_array()
+            .ok_or(format_err!(
+                "Failed to parse 'package' tables of poetry.lock"
+            ))?
+            .clone(),
+        None => Vec::new(),
+    };
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        let name = package
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(format_err!("Failed to parse package name in poetry.lock"))?;
+        if arguments.production_only && is_development_package(&package) {
+            continue;
+        }
+        let package_markers = get_markers(&package);
+        if !package_markers.is_empty() {
+            let mut applies = false;
+            for package_marker in package_markers {
+                if marker::evaluate(&package_marker, &environment, &BTreeSet::new())
+                    .context(format!("Failed to evaluate markers of package: {}", name))?
+                {
+                    applies = true;
+                    break;
+                }
+            }
+            if !applies {
+                continue;
+            }
+        }
+        let host_name = get_source_host_name(&package)
+            .context(format!("Failed to parse source of package: {}", name))?;
+
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: name.to_string(),
+                    version: get_parsed_version(&package),
+                },
+                location::Location::find(&file_path, &content, &vec!["[[package]]"; index + 1])
+                    .with_key_path(&format!("package[{}]", index)),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_host_names() {
+        let lock: toml::Value = toml::from_str(
+            r#"
+            [[package]]
+            name = "requests"
+            version = "2.31.0"
+
+            [[package]]
+            name = "internal"
+            version = "1.0"
+            [package.source]
+            type = "legacy"
+            url = "https://pypi.example.com/simple"
+
+            [[package]]
+            name = "mirrored"
+            version = "1.0"
+            [package.source]
+            type = "legacy"
+            url = "https://files.pythonhosted.org/simple"
+
+            [[package]]
+            name = "repo"
+            version = "1.0"
+            [package.source]
+            type = "git"
+            url = "https://github.com/owner/repo.git"
+            resolved_reference = "abc123"
+
+            [[package]]
+            name = "local"
+            version = "1.0"
+            [package.source]
+            type = "directory"
+            url = "../local"
+            "#,
+        )
+        .unwrap();
+        let packages: Vec<(String, vouch_lib::extension::common::VersionParseResult)> = lock
+            ["package"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| {
+                (
+                    get_source_host_name(&package).unwrap(),
+                    get_parsed_version(&package),
+                )
+            })
+            .collect();
+        assert_eq!(
+            packages,
+            vec![
+                ("pypi.org".to_string(), Ok("2.31.0".to_string())),
+                ("pypi.example.com".to_string(), Ok("1.0".to_string())),
+                ("pypi.org".to_string(), Ok("1.0".to_string())),
+                ("github.com".to_string(), Ok("abc123".to_string())),
+                (source::LOCAL_HOST_NAME.to_string(), Ok("1.0".to_string())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{arguments, environment, location, marker, requirements, source};
+
+/// Returns registry host name and version for package.
+///
+/// Registry packages are attributed to the host of their `index` URL. Without an index, the host
+/// of the first sdist or wheel URL is used.
+fn get_host_name_and_version(
+    package: &toml::Value,
+) -> Result<(String, vouch_lib::extension::common::VersionParseResult)> {
+    let version = match package.get("version").and_then(|v| v.as_str()) {
+        Some(v) => Ok(v.to_string()),
+        None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+    };
+
+    // VCS sources are pinned by their commit.
+    if let Some(vcs) = package.get("vcs") {
+        let version = match vcs.get("commit-id").and_then(|v| v.as_str()) {
+            Some(v) => Ok(v.to_string()),
+            None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+        };
+        return match vcs.get("url").and_then(|v| v.as_str()) {
+            Some(url) => Ok((source::get_host_name(&url)?, version)),
+            None => Ok((source::LOCAL_HOST_NAME.to_string(), version)),
+        };
+    }
+    if package.get("directory").is_some() {
+        return Ok((source::LOCAL_HOST_NAME.to_string(), version));
+    }
+    if let Some(archive) = package.get("archive") {
+        return match archive.get("url").and_then(|v| v.as_str()) {
+            Some(url) => Ok((source::get_host_name(&url)?, version)),
+            None => Ok((source::LOCAL_HOST_NAME.to_string(), version)),
+        };
+    }
+
+    if let Some(index_url) = package.get("index").and_then(|v| v.as_str()) {
+        return Ok((source::get_index_host_name(&index_url)?, version));
+    }
+    let distribution_url = package
+        .get("sdist")
+        .and_then(|sdist| sdist.get("url"))
+        .or(package
+            .get("wheels")
+            .and_then(|wheels| wheels.get(0))
+            .and_then(|wheel| wheel.get("url")))
+        .and_then(|v| v.as_str());
+    match distribution_url {
+        Some(url) => Ok((source::get_index_host_name(&url)?, version)),
+        None => Ok((source::PYPI_HOST_NAME.to_string(), version)),
+    }
+}
+
+/// Returns the normalized names listed by the given top level array of the lock file.
+fn get_names(lock: &toml::Value, key: &str) -> BTreeSet<String> {
+    lock.get(key)
+        .and_then(|v| v.as_array())
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.as_str())
+                .map(|name| requirements::normalize_name(&name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Returns one file defined dependencies structure per registry host. Packages whose `marker` does
+/// not hold within the target environment are skipped. Markers are evaluated for an install of
+/// all the lock file's `extras` and `dependency-groups`. Dependency groups are development
+/// dependencies and are not installed if `production_only` is set.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let lock: toml::Value = toml::from_str(&content).context(format!(
+        "Failed to parse pylock.toml: {}",
+        file_path.display()
+    ))?;
+
+    let lock_version = lock
+        .get("lock-version")
+        .and_then(|v| v.as_str())
+        .ok_or(format_err!(
+            "Failed to parse lock-version of file: {}",
+            file_path.display()
+        ))?;
+    if !lock_version.starts_with("1.") {
+        return Err(format_err!(
+            "Unsupported pylock.toml lock-version '{}': {}",
+            lock_version,
+            file_path.display()
+        ));
+    }
+
+    let packages = match lock.get("packages") {
+        Some(v) => v
+            .as_array()
+            .ok_or(format_err!(
+                "Failed to parse 'packages' tables of pylock.toml"
+            ))?
+            .clone(),
+        None => Vec::new(),
+    };
+
+    let extras = get_names(&lock, "extras");
+    let dependency_groups = if arguments.production_only {
+        BTreeSet::new()
+    } else {
+        get_names(&lock, "dependency-groups")
+    };
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        let name = package
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(format_err!("Failed to parse package name in pylock.toml"))?;
+        if let Some(package_marker) = package.get("marker").and_then(|v| v.as_str()) {
+            if !marker::evaluate_lock(&package_marker, &environment, &extras, &dependency_groups)
+                .context(format!("Failed to evaluate marker of package: {}", name))?
+            {
+                continue;
+            }
+        }
+        let (host_name, version) = get_host_name_and_version(&package)
+            .context(format!("Failed to parse source of package: {}", name))?;
+
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: name.to_string(),
+                    version,
+                },
+                location::Location::find(&file_path, &content, &vec!["[[packages]]"; index + 1])
+                    .with_key_path(&format!("packages[{}]", index)),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+/// Returns lock files found within the given directory.
+///
+/// Lock files are named `pylock.toml` or `pylock.<name>.toml`.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_lock_file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with("pylock.") && name.ends_with(".toml")
+                });
+            if path.is_file() && is_lock_file_name {
+                file_paths.push(path);
+            }
+        }
+    }
+    file_paths.sort();
+    file_paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_names_and_markers() {
+        let directory = std::env::temp_dir().join("vouch-py-pylock");
+        std::fs::create_dir_all(&directory).unwrap();
+        let file_path = directory.join("pylock.toml");
+        std::fs::write(
+            &file_path,
+            r#"
+            lock-version = "1.0"
+            created-by = "test"
+            dependency-groups = ["dev"]
+            default-groups = []
+
+            [[packages]]
+            name = "requests"
+            version = "2.31.0"
+            index = "https://pypi.org/simple"
+
+            [[packages]]
+            name = "mirrored"
+            version = "1.0"
+            sdist = { url = "https://files.pythonhosted.org/packages/mirrored-1.0.tar.gz" }
+
+            [[packages]]
+            name = "pytest"
+            version = "8.0.0"
+            marker = "'dev' in dependency_groups"
+            index = "https://pypi.example.com/simple"
+
+            [[packages]]
+            name = "repo"
+            vcs = { type = "git", url = "https://github.com/owner/repo.git", commit-id = "abc123" }
+
+            [[packages]]
+            name = "local"
+            directory = { path = "../local" }
+
+            [[packages]]
+            name = "pywin32"
+            version = "306"
+            marker = "sys_platform == 'win32'"
+            "#,
+        )
+        .unwrap();
+        let environment =
+            environment::Environment::new("3.12", "linux", "x86_64", "cpython").unwrap();
+
+        let get_dependencies = |arguments: &arguments::Arguments| {
+            let mut dependencies: Vec<(String, String)> =
+                get_file_defined_dependencies(&file_path, &arguments, &environment)
+                    .unwrap()
+                    .into_iter()
+                    .flat_map(|file| {
+                        let host_name = file.registry_host_name.clone();
+                        file.dependencies
+                            .into_keys()
+                            .map(move |dependency| (host_name.clone(), dependency.name))
+                    })
+                    .collect();
+            dependencies.sort();
+            dependencies
+        };
+        let expected = |names: &[(&str, &str)]| -> Vec<(String, String)> {
+            names
+                .iter()
+                .map(|(host_name, name)| (host_name.to_string(), name.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            get_dependencies(&arguments::Arguments::default()),
+            expected(&[
+                ("github.com", "repo"),
+                (source::LOCAL_HOST_NAME, "local"),
+                ("pypi.example.com", "pytest"),
+                ("pypi.org", "mirrored"),
+                ("pypi.org", "requests"),
+            ])
+        );
+        let arguments = arguments::Arguments {
+            production_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_dependencies(&arguments),
+            expected(&[
+                ("github.com", "repo"),
+                (source::LOCAL_HOST_NAME, "local"),
+                ("pypi.org", "mirrored"),
+                ("pypi.org", "requests"),
+            ])
+        );
+    }
+}
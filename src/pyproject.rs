@@ -0,0 +1,138 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{arguments, environment, location, requirements, source};
+
+/// Returns requirement strings from a TOML array and their key paths.
+fn get_requirement_strings(value: &toml::Value, section: &str) -> Result<Vec<(String, String)>> {
+    let array = value.as_array().ok_or(format_err!(
+        "Failed to parse '{}' array of pyproject.toml",
+        section
+    ))?;
+    array
+        .iter()
+        .enumerate()
+        // Dependency groups may include other groups, which are reported themselves.
+        .filter(|(_, requirement)| requirement.get("include-group").is_none())
+        .map(|(index, requirement)| {
+            requirement
+                .as_str()
+                .map(|v| (format!("{}[{}]", section, index), v.to_string()))
+                .ok_or(format_err!(
+                    "Failed to parse requirement string in '{}' of pyproject.toml",
+                    section
+                ))
+        })
+        .collect()
+}
+
+/// Returns the names of the development dependency groups defined by a pyproject.toml file.
+///
+/// Development groups are the PEP 735 `[dependency-groups]` and the
+/// `[tool.pdm.dev-dependencies]` groups. Names are normalized.
+pub fn get_development_group_names(pyproject: &toml::Value) -> BTreeSet<String> {
+    let dependency_groups = pyproject.get("dependency-groups");
+    let pdm_dev_dependencies = pyproject
+        .get("tool")
+        .and_then(|tool| tool.get("pdm"))
+        .and_then(|pdm| pdm.get("dev-dependencies"));
+    vec![dependency_groups, pdm_dev_dependencies]
+        .into_iter()
+        .flatten()
+        .filter_map(|groups| groups.as_table())
+        .flat_map(|groups| groups.keys())
+        .map(|group| requirements::normalize_name(&group))
+        .collect()
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Includes `[project].dependencies`, all `[project.optional-dependencies]` groups, all PEP 735
+/// `[dependency-groups]` and `[build-system].requires`. Build backends run code on install and
+/// are therefore included. Requirements which do not apply within the target environment are
+/// excluded. Dependency groups are development dependencies and are excluded if
+/// `production_only` is set.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let pyproject: toml::Value = toml::from_str(&content).context(format!(
+        "Failed to parse pyproject.toml: {}",
+        file_path.display()
+    ))?;
+
+    let mut requirement_strings = Vec::new();
+    if let Some(project) = pyproject.get("project") {
+        if let Some(dependencies) = project.get("dependencies") {
+            requirement_strings.extend(get_requirement_strings(
+                &dependencies,
+                "project.dependencies",
+            )?);
+        }
+        if let Some(optional_dependencies) = project.get("optional-dependencies") {
+            let groups = optional_dependencies.as_table().ok_or(format_err!(
+                "Failed to parse 'project.optional-dependencies' table of pyproject.toml"
+            ))?;
+            for (group, dependencies) in groups {
+                requirement_strings.extend(get_requirement_strings(
+                    &dependencies,
+                    &format!("project.optional-dependencies.{}", group),
+                )?);
+            }
+        }
+    }
+    let dependency_groups = if arguments.production_only {
+        None
+    } else {
+        pyproject.get("dependency-groups")
+    };
+    if let Some(dependency_groups) = dependency_groups {
+        let groups = dependency_groups.as_table().ok_or(format_err!(
+            "Failed to parse 'dependency-groups' table of pyproject.toml"
+        ))?;
+        for (group, dependencies) in groups {
+            requirement_strings.extend(get_requirement_strings(
+                &dependencies,
+                &format!("dependency-groups.{}", group),
+            )?);
+        }
+    }
+    if let Some(requires) = pyproject
+        .get("build-system")
+        .and_then(|build_system| build_system.get("requires"))
+    {
+        requirement_strings.extend(get_requirement_strings(&requires, "build-system.requires")?);
+    }
+
+    let mut all_dependencies = BTreeMap::new();
+    all_dependencies.insert(
+        source::PYPI_HOST_NAME.to_string(),
+        location::Dependencies::new(),
+    );
+    for (key_path, requirement_string) in requirement_strings {
+        let requirement = requirements::parse_requirement(&requirement_string).context(format!(
+            "Failed to parse requirement '{}' in file: {}",
+            requirement_string,
+            file_path.display()
+        ))?;
+        if !requirements::applies(&requirement, &environment)? {
+            continue;
+        }
+        let mut patterns: Vec<&str> = key_path.split(|c| c == '.' || c == '[').collect();
+        patterns.pop();
+        patterns.push(&requirement_string);
+        all_dependencies
+            .entry(requirements::get_host_name(
+                &requirement,
+                source::PYPI_HOST_NAME,
+            ))
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                requirements::to_dependency(&requirement),
+                location::Location::find(&file_path, &content, &patterns).with_key_path(&key_path),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
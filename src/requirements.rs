@@ -0,0 +1,765 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::{environment, location, marker, source, version_error};
+
+/// URL scheme prefixes of version control system requirements (e.g. `git+https://...`).
+static VCS_PREFIXES: &[&str] = &["git+", "hg+", "svn+", "bzr+"];
+
+/// A single PEP 508 requirement.
+#[derive(Debug, Clone, Default)]
+pub struct Requirement {
+    pub name: String,
+    pub extras: Vec<String>,
+    pub specifier: String,
+    pub url: Option<String>,
+    pub marker: Option<String>,
+}
+
+/// Normalize package name as described in PEP 503.
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::new();
+    let mut previous_was_separator = false;
+    for c in name.trim().chars() {
+        if c == '-' || c == '_' || c == '.' {
+            if !previous_was_separator {
+                normalized.push('-');
+            }
+            previous_was_separator = true;
+        } else {
+            normalized.extend(c.to_lowercase());
+            previous_was_separator = false;
+        }
+    }
+    normalized
+}
+
+/// Parse a PEP 508 requirement string.
+///
+/// Example: `requests[socks] >=2.8.1, ==2.8.* ; python_version < "2.7"`
+pub fn parse_requirement(requirement: &str) -> Result<Requirement> {
+    let requirement = requirement.trim();
+
+    let name_length = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_length];
+    if name.is_empty() {
+        return Err(format_err!(
+            "Failed to parse requirement package name: {}",
+            requirement
+        ));
+    }
+    let mut remainder = requirement[name_length..].trim_start();
+
+    let mut extras = Vec::new();
+    if let Some(v) = remainder.strip_prefix('[') {
+        let end = v.find(']').ok_or(format_err!(
+            "Failed to parse requirement extras: {}",
+            requirement
+        ))?;
+        extras = v[..end]
+            .split(',')
+            .map(normalize_name)
+            .filter(|extra| !extra.is_empty())
+            .collect();
+        remainder = v[end + 1..].trim_start();
+    }
+
+    // URL requirements must separate the marker from the URL with whitespace.
+    if let Some(v) = remainder.strip_prefix('@') {
+        let v = v.trim();
+        let (url, marker) = match v.find(" ;").or(v.find("\t;")) {
+            Some(index) => (v[..index].trim(), get_marker(&v[index..])),
+            None => (v, None),
+        };
+        return Ok(Requirement {
+            name: normalize_name(name),
+            extras,
+            specifier: String::new(),
+            url: Some(url.to_string()),
+            marker,
+        });
+    }
+
+    let (specifier, marker) = match remainder.find(';') {
+        Some(index) => (&remainder[..index], get_marker(&remainder[index..])),
+        None => (remainder, None),
+    };
+    let specifier = specifier
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    Ok(Requirement {
+        name: normalize_name(name),
+        extras,
+        specifier,
+        url: None,
+        marker,
+    })
+}
+
+/// Returns the environment marker following the `;` separator, if any.
+fn get_marker(value: &str) -> Option<String> {
+    let marker = value.trim_start().trim_start_matches(';').trim();
+    if marker.is_empty() {
+        None
+    } else {
+        Some(marker.to_string())
+    }
+}
+
+/// Returns true if the requirement URL is a local file or path.
+///
+/// Examples: `./packages/foo`, `foo-1.0-py3-none-any.whl`, `file:///tmp/foo.tar.gz`
+fn is_local_url(url: &str) -> bool {
+    url.starts_with("file:") || source::get_host_name(&url).is_err()
+}
+
+/// Returns the revision of a VCS URL, if given.
+///
+/// Example: `v1.0` for `git+https://github.com/owner/repo.git@v1.0#egg=repo`
+fn get_vcs_revision(url: &str) -> Option<String> {
+    let url = url.split('#').next().unwrap_or_default();
+    // The revision follows the repository path, which may itself follow a `user@` prefix.
+    let path_start = match url.find("://") {
+        Some(index) => index + 3 + url[index + 3..].find('/')?,
+        None => url.find(':')?,
+    };
+    let path = &url[path_start..];
+    let revision = &path[path.rfind('@')? + 1..];
+    if revision.is_empty() {
+        None
+    } else {
+        Some(revision.to_string())
+    }
+}
+
+/// Returns registry host name of the requirement.
+///
+/// Requirements given by URL are attributed to the URL host, or to the local host name for local
+/// files and paths. Other requirements are attributed to the given package index host name.
+pub fn get_host_name(requirement: &Requirement, index_host_name: &str) -> String {
+    match &requirement.url {
+        Some(url) if is_local_url(&url) => source::LOCAL_HOST_NAME.to_string(),
+        Some(url) => source::get_host_name(&url).unwrap_or(source::LOCAL_HOST_NAME.to_string()),
+        None => index_host_name.to_string(),
+    }
+}
+
+/// Parse and clean requirement version.
+///
+/// Only exact pins (`==` or `===`) are accepted as versions. VCS requirements are versioned by
+/// their revision. Local file and path requirements are reported with an error which notes that
+/// they can not be reviewed from a registry. Returns a structure which details common errors.
+pub fn get_parsed_version(
+    requirement: &Requirement,
+) -> vouch_lib::extension::common::VersionParseResult {
+    if let Some(url) = &requirement.url {
+        if is_local_url(&url) {
+            return Err(
+                vouch_lib::extension::common::VersionError::from_parse_error(
+                    version_error::LOCAL_SOURCE_ERROR,
+                ),
+            );
+        }
+        if VCS_PREFIXES.iter().any(|prefix| url.starts_with(prefix)) {
+            return get_vcs_revision(&url)
+                .ok_or(vouch_lib::extension::common::VersionError::from_missing_version());
+        }
+        return Err(vouch_lib::extension::common::VersionError::from_parse_error(url));
+    }
+    let specifier = requirement.specifier.as_str();
+    if specifier.is_empty() {
+        return Err(vouch_lib::extension::common::VersionError::from_missing_version());
+    }
+
+    let version = specifier
+        .strip_prefix("===")
+        .or(specifier.strip_prefix("=="));
+    match version {
+        Some(v) if !v.is_empty() && !v.contains(',') && !v.contains('*') => Ok(v.to_string()),
+        _ => Err(vouch_lib::extension::common::VersionError::from_parse_error(specifier)),
+    }
+}
+
+/// Returns true if the requirement applies within the target environment.
+///
+/// Requirements without an environment marker always apply.
+pub fn applies(requirement: &Requirement, environment: &environment::Environment) -> Result<bool> {
+    match &requirement.marker {
+        Some(marker) => marker::evaluate(&marker, &environment, &BTreeSet::new()),
+        None => Ok(true),
+    }
+}
+
+/// Returns a dependency which reports a requirements file installed with `-r` as not found.
+///
+/// Example dependency name: `-r requirements/dev.txt`
+pub fn to_missing_file_dependency(requirements_file: &str) -> vouch_lib::extension::Dependency {
+    vouch_lib::extension::Dependency {
+        name: format!("-r {}", requirements_file),
+        version: Err(
+            vouch_lib::extension::common::VersionError::from_parse_error(
+                version_error::MISSING_REQUIREMENTS_FILE_ERROR,
+            ),
+        ),
+    }
+}
+
+/// Convert a requirement into a dependency.
+pub fn to_dependency(requirement: &Requirement) -> vouch_lib::extension::Dependency {
+    vouch_lib::extension::Dependency {
+        name: requirement.name.clone(),
+        version: get_parsed_version(&requirement),
+    }
+}
+
+/// Remove comment from line.
+///
+/// A comment starts with '#' at the start of the line or after whitespace.
+fn strip_comment(line: &str) -> &str {
+    let mut previous: Option<char> = None;
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.map_or(true, |p| p.is_whitespace()) {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+/// Remove per-requirement options (e.g. `--hash=sha256:...`) from requirement line.
+fn strip_requirement_options(line: &str) -> &str {
+    let mut previous: Option<char> = None;
+    for (index, c) in line.char_indices() {
+        if c == '-'
+            && line[index..].starts_with("--")
+            && previous.map_or(false, |p| p.is_whitespace())
+        {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+/// Returns logical lines and their starting line numbers.
+///
+/// Joins lines ending with a backslash and removes comments.
+pub fn get_logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut logical_lines = Vec::new();
+    let mut current_line = String::new();
+    let mut current_line_number: Option<usize> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        if current_line_number.is_none() {
+            current_line_number = Some(index + 1);
+        }
+        match line.strip_suffix('\\') {
+            Some(v) => {
+                current_line.push_str(strip_comment(v));
+                current_line.push(' ');
+            }
+            None => {
+                current_line.push_str(strip_comment(line));
+                let line = current_line.trim().to_string();
+                if !line.is_empty() {
+                    logical_lines.push((current_line_number.unwrap(), line));
+                }
+                current_line = String::new();
+                current_line_number = None;
+            }
+        }
+    }
+    let line = current_line.trim().to_string();
+    if let (Some(line_number), false) = (current_line_number, line.is_empty()) {
+        logical_lines.push((line_number, line));
+    }
+    logical_lines
+}
+
+/// Returns true if requirement line is a URL or path rather than a named requirement.
+fn is_url_or_path(line: &str) -> bool {
+    let first_token = line
+        .split(|c: char| c.is_whitespace() || c == '@' || c == '[' || c == ';')
+        .next()
+        .unwrap_or("");
+    first_token.contains(':')
+        || first_token.contains('/')
+        || first_token.contains('\\')
+        || first_token.starts_with('.')
+        || first_token.starts_with('~')
+        || first_token.ends_with(".whl")
+        || first_token.ends_with(".tar.gz")
+        || first_token.ends_with(".zip")
+}
+
+/// Parse URL or path requirement, e.g. `git+https://github.com/owner/repo.git@v1.0#egg=repo`.
+///
+/// The requirement is named by the `#egg=` URL fragment if given, otherwise by the URL itself.
+fn parse_url_requirement(line: &str) -> Requirement {
+    let (url, marker) = match line.find(" ;").or(line.find("\t;")) {
+        Some(index) => (line[..index].trim(), get_marker(&line[index..])),
+        None => (line, None),
+    };
+    let egg_name = url.split_once('#').and_then(|(_, fragment)| {
+        fragment
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("egg="))
+            .filter(|name| !name.is_empty())
+    });
+    Requirement {
+        name: egg_name.map(normalize_name).unwrap_or(url.to_string()),
+        url: Some(url.to_string()),
+        marker: marker,
+        ..Default::default()
+    }
+}
+
+/// Parse requirements file line.
+///
+/// URL, path and editable (`-e`) requirements are returned with their URL. Returns None for other
+/// option lines (e.g. `--index-url`).
+pub fn parse_requirement_line(line: &str) -> Result<Option<Requirement>> {
+    let editable = line
+        .strip_prefix("--editable")
+        .or(line.strip_prefix("-e"))
+        .map(|v| v.trim_start_matches('=').trim());
+    if let Some(editable) = editable {
+        let editable = strip_requirement_options(&editable).trim();
+        if editable.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(parse_url_requirement(&editable)));
+    }
+    if line.starts_with('-') {
+        return Ok(None);
+    }
+    let line = strip_requirement_options(&line).trim();
+    if is_url_or_path(&line) {
+        return Ok(Some(parse_url_requirement(&line)));
+    }
+    Ok(Some(parse_requirement(&line)?))
+}
+
+/// Returns included file path and true if the include is a constraints file.
+///
+/// Recognises `-r`, `--requirement`, `-c` and `--constraint` options.
+pub fn parse_include_option(line: &str) -> Option<(String, bool)> {
+    for (option, is_constraint) in vec![
+        ("--requirement", false),
+        ("--constraint", true),
+        ("-r", false),
+        ("-c", true),
+    ] {
+        if let Some(v) = line.strip_prefix(option) {
+            let v = v.trim_start_matches('=').trim();
+            if v.is_empty() {
+                return None;
+            }
+            return Some((v.to_string(), is_constraint));
+        }
+    }
+    None
+}
+
+/// Requirements file and the requirements which it declares, with their line numbers.
+#[derive(Debug, Clone)]
+struct RequirementsFile {
+    path: std::path::PathBuf,
+    is_constraint: bool,
+    requirements: Vec<(usize, Requirement)>,
+}
+
+/// Parse requirements file and recursively follow its includes.
+///
+/// Included file paths are relative to the including file. Files which have already been
+/// collected are skipped. Includes which lead back to a file on the current include stack are
+/// reported as errors. Requirements which do not apply within the target environment are
+/// skipped.
+fn collect_files(
+    file_path: &std::path::PathBuf,
+    is_constraint: bool,
+    environment: &environment::Environment,
+    include_stack: &mut Vec<std::path::PathBuf>,
+    files: &mut Vec<RequirementsFile>,
+) -> Result<()> {
+    let file_path = std::fs::canonicalize(&file_path).context(format!(
+        "Failed to find requirements file: {}",
+        file_path.display()
+    ))?;
+    if include_stack.contains(&file_path) {
+        let cycle = include_stack
+            .iter()
+            .skip_while(|path| **path != file_path)
+            .chain(std::iter::once(&file_path))
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        return Err(format_err!(
+            "Found requirements file include cycle: {}",
+            cycle.join(" -> ")
+        ));
+    }
+    if files.iter().any(|file| file.path == file_path) {
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(&file_path).context(format!(
+        "Failed to read requirements file: {}",
+        file_path.display()
+    ))?;
+    let parent_directory = file_path
+        .parent()
+        .ok_or(format_err!(
+            "Failed to find parent directory of file: {}",
+            file_path.display()
+        ))?
+        .to_path_buf();
+
+    include_stack.push(file_path.clone());
+    let mut requirements = Vec::new();
+    for (line_number, line) in get_logical_lines(&content) {
+        if let Some((include_path, is_include_constraint)) = parse_include_option(&line) {
+            // Remote requirements files are not followed.
+            if include_path.contains("://") {
+                continue;
+            }
+            // Requirements files included by a constraints file are also constraints.
+            collect_files(
+                &parent_directory.join(&include_path),
+                is_constraint || is_include_constraint,
+                environment,
+                include_stack,
+                files,
+            )
+            .context(format!(
+                "Failed to follow include on line {} of file: {}",
+                line_number,
+                file_path.display()
+            ))?;
+            continue;
+        }
+
+        let requirement = parse_requirement_line(&line).context(format!(
+            "Failed to parse requirement on line {} of file: {}",
+            line_number,
+            file_path.display()
+        ))?;
+        if let Some(requirement) = requirement {
+            let is_applicable = applies(&requirement, &environment).context(format!(
+                "Failed to evaluate requirement on line {} of file: {}",
+                line_number,
+                file_path.display()
+            ))?;
+            if is_applicable {
+                requirements.push((line_number, requirement));
+            }
+        }
+    }
+    include_stack.pop();
+
+    files.push(RequirementsFile {
+        path: file_path,
+        is_constraint,
+        requirements,
+    });
+    Ok(())
+}
+
+/// Parse dependencies from requirements files and the files which they include.
+///
+/// Returns one file defined dependencies structure per contributing file and registry host. URL
+/// and path requirements are attributed to their source host. Versions pinned within
+/// constraints files are used for unpinned requirements. Constraints files list the constrained
+/// dependencies which they pinned. Requirements which do not apply within the target environment
+/// are excluded.
+///
+/// Given files which fail to parse, or which include a file which fails to parse, are returned
+/// with their error instead. The remaining files are still parsed together.
+pub fn get_file_defined_dependencies(
+    file_paths: &Vec<std::path::PathBuf>,
+    environment: &environment::Environment,
+) -> (
+    Vec<location::FileDefinedDependencies>,
+    Vec<(std::path::PathBuf, anyhow::Error)>,
+) {
+    let mut files_closures = Vec::new();
+    let mut file_errors = Vec::new();
+    for file_path in file_paths {
+        let mut files = Vec::new();
+        match collect_files(&file_path, false, &environment, &mut Vec::new(), &mut files) {
+            Ok(_) => files_closures.push(files),
+            Err(error) => file_errors.push((file_path.clone(), error)),
+        }
+    }
+
+    // Files which are included by another given file are reported as part of that file's closure.
+    let root_closures = files_closures.iter().enumerate().filter(|(index, files)| {
+        let root_path = &files.last().expect("closure includes its root file").path;
+        !files_closures
+            .iter()
+            .enumerate()
+            .any(|(other_index, other_files)| {
+                other_index != *index
+                    && other_files
+                        .iter()
+                        .rev()
+                        .skip(1)
+                        .any(|file| file.path == *root_path)
+            })
+    });
+
+    let mut reported_paths = HashSet::new();
+    let mut all_file_defined_dependencies = Vec::new();
+    for (_, files) in root_closures {
+        let mut constraints = std::collections::HashMap::new();
+        for file in files.iter().filter(|file| file.is_constraint) {
+            for (_, requirement) in file.requirements.iter().filter(|(_, r)| r.url.is_none()) {
+                if let Ok(version) = get_parsed_version(&requirement) {
+                    constraints.insert(requirement.name.clone(), version);
+                }
+            }
+        }
+        let required_names = files
+            .iter()
+            .filter(|file| !file.is_constraint)
+            .flat_map(|file| file.requirements.iter().map(|(_, r)| r.name.clone()))
+            .collect::<HashSet<_>>();
+
+        for file in files {
+            if !reported_paths.insert(file.path.clone()) {
+                continue;
+            }
+
+            // Each file is reported, even if it declares no dependencies.
+            let mut all_dependencies = BTreeMap::new();
+            all_dependencies.insert(
+                source::PYPI_HOST_NAME.to_string(),
+                location::Dependencies::new(),
+            );
+            for (line_number, requirement) in &file.requirements {
+                if file.is_constraint && !required_names.contains(&requirement.name) {
+                    continue;
+                }
+                let mut dependency = to_dependency(&requirement);
+                // Constraints pin unpinned named requirements, but not URL requirements.
+                if !file.is_constraint && requirement.url.is_none() && dependency.version.is_err() {
+                    if let Some(version) = constraints.get(&requirement.name) {
+                        dependency.version = Ok(version.clone());
+                    }
+                }
+                all_dependencies
+                    .entry(get_host_name(&requirement, source::PYPI_HOST_NAME))
+                    .or_insert_with(location::Dependencies::new)
+                    .insert(
+                        dependency,
+                        location::Location::from_line(&file.path, *line_number),
+                    );
+            }
+            all_file_defined_dependencies
+                .extend(location::from_registry_hosts(&file.path, all_dependencies));
+        }
+    }
+    (all_file_defined_dependencies, file_errors)
+}
+
+/// Returns requirements files found within the given directory.
+///
+/// Includes files such as `requirements.txt` and `dev-requirements.txt` and all text files within
+/// a `requirements` subdirectory.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    let is_text_file = |path: &std::path::PathBuf| {
+        path.is_file() && path.extension().map_or(false, |e| e == "txt")
+    };
+
+    if let Ok(entries) = std::fs::read_dir(&directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_requirements_file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.contains("requirements"));
+            if is_text_file(&path) && is_requirements_file_name {
+                file_paths.push(path);
+            }
+        }
+    }
+    if let Ok(entries) = std::fs::read_dir(directory.join("requirements")) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if is_text_file(&path) {
+                file_paths.push(path);
+            }
+        }
+    }
+
+    file_paths.sort();
+    file_paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Requirement {
+        parse_requirement_line(&line).unwrap().unwrap()
+    }
+
+    #[test]
+    fn vcs_requirements() {
+        let requirement = parse_line("git+https://github.com/owner/repo.git@v1.0#egg=Repo_Name");
+        assert_eq!(requirement.name, "repo-name");
+        assert_eq!(get_host_name(&requirement, "pypi.org"), "github.com");
+        assert_eq!(get_parsed_version(&requirement), Ok("v1.0".to_string()));
+
+        let requirement = parse_line("-e git+ssh://git@gitlab.com/owner/repo.git@abc123#egg=repo");
+        assert_eq!(get_host_name(&requirement, "pypi.org"), "gitlab.com");
+        assert_eq!(get_parsed_version(&requirement), Ok("abc123".to_string()));
+
+        let requirement =
+            parse_line("repo @ git+https://github.com/owner/repo.git ; os_name == 'nt'");
+        assert_eq!(requirement.name, "repo");
+        assert_eq!(requirement.marker, Some("os_name == 'nt'".to_string()));
+        assert!(get_parsed_version(&requirement).is_err());
+    }
+
+    #[test]
+    fn local_requirements() {
+        for line in &[
+            "-e .",
+            "--editable=./packages/foo",
+            "./foo-1.0-py3-none-any.whl",
+            "file:///tmp/foo",
+        ] {
+            let requirement = parse_line(&line);
+            assert!(requirement.url.is_some(), "{}", line);
+            assert_eq!(
+                get_host_name(&requirement, "pypi.org"),
+                source::LOCAL_HOST_NAME
+            );
+            assert_eq!(
+                get_parsed_version(&requirement),
+                Err(
+                    vouch_lib::extension::common::VersionError::from_parse_error(
+                        version_error::LOCAL_SOURCE_ERROR
+                    )
+                ),
+                "{}",
+                line
+            );
+        }
+    }
+
+    /// Returns the dependencies of each file, as sorted `name==version` strings keyed by file
+    /// name and registry host.
+    fn get_file_dependencies(
+        file_defined_dependencies: &Vec<location::FileDefinedDependencies>,
+    ) -> BTreeMap<(String, String), Vec<String>> {
+        file_defined_dependencies
+            .iter()
+            .map(|file| {
+                let mut dependencies: Vec<String> = file
+                    .dependencies
+                    .keys()
+                    .map(|dependency| match &dependency.version {
+                        Ok(version) => format!("{}=={}", dependency.name, version),
+                        Err(_) => dependency.name.clone(),
+                    })
+                    .collect();
+                dependencies.sort();
+                (
+                    (
+                        file.path.file_name().unwrap().to_string_lossy().to_string(),
+                        file.registry_host_name.clone(),
+                    ),
+                    dependencies,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn includes_and_constraints() {
+        let directory = std::env::temp_dir().join("vouch-py-requirements-includes");
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, content) in &[
+            (
+                "requirements.txt",
+                "-r base.txt\n-c constraints.txt\nflask\n-e git+https://github.com/owner/repo.git@v1.0#egg=repo\n",
+            ),
+            ("base.txt", "requests\nidna==3.4\n"),
+            (
+                "constraints.txt",
+                "requests==2.31.0\nflask==3.0.0\nurllib3==2.0.7\nrepo==2.0\n",
+            ),
+        ] {
+            std::fs::write(directory.join(name), content).unwrap();
+        }
+        let environment =
+            environment::Environment::new("3.12", "linux", "x86_64", "cpython").unwrap();
+
+        let (file_defined_dependencies, file_errors) = get_file_defined_dependencies(
+            &vec![
+                directory.join("requirements.txt"),
+                directory.join("base.txt"),
+            ],
+            &environment,
+        );
+        assert!(file_errors.is_empty());
+        let pypi = source::PYPI_HOST_NAME.to_string();
+        let expected: BTreeMap<(String, String), Vec<String>> = vec![
+            (("requirements.txt", pypi.as_str()), vec!["flask==3.0.0"]),
+            (("requirements.txt", "github.com"), vec!["repo==v1.0"]),
+            (
+                ("base.txt", pypi.as_str()),
+                vec!["idna==3.4", "requests==2.31.0"],
+            ),
+            (
+                ("constraints.txt", pypi.as_str()),
+                vec!["flask==3.0.0", "repo==2.0", "requests==2.31.0"],
+            ),
+        ]
+        .into_iter()
+        .map(|((name, host), dependencies)| {
+            (
+                (name.to_string(), host.to_string()),
+                dependencies.iter().map(|v| v.to_string()).collect(),
+            )
+        })
+        .collect();
+        assert_eq!(get_file_dependencies(&file_defined_dependencies), expected);
+
+        std::fs::write(directory.join("broken.txt"), "-r missing.txt\n").unwrap();
+        let (file_defined_dependencies, file_errors) = get_file_defined_dependencies(
+            &vec![directory.join("broken.txt"), directory.join("base.txt")],
+            &environment,
+        );
+        assert_eq!(file_errors.len(), 1);
+        assert_eq!(file_errors[0].0, directory.join("broken.txt"));
+        assert_eq!(
+            get_file_dependencies(&file_defined_dependencies)
+                .keys()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["base.txt"]
+        );
+    }
+
+    #[test]
+    fn named_requirements() {
+        let requirement = parse_line("Requests[socks]==2.0 --hash=sha256:abc");
+        assert_eq!(requirement.name, "requests");
+        assert_eq!(get_host_name(&requirement, "example.com"), "example.com");
+        assert_eq!(get_parsed_version(&requirement), Ok("2.0".to_string()));
+        assert!(parse_requirement_line("--index-url https://example.com")
+            .unwrap()
+            .is_none());
+        assert!(parse_requirement_line("-e").unwrap().is_none());
+    }
+}
@@ -0,0 +1,682 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::{environment, marker, pep440, requirements};
+
+// Maximum number of candidate versions tried before resolution is abandoned.
+static MAX_ATTEMPTS: usize = 5000;
+
+/// Distribution file of a package release.
+#[derive(Debug, Clone)]
+pub struct ReleaseFile {
+    pub filename: String,
+    // Distribution type (e.g. `sdist` or `bdist_wheel`).
+    pub package_type: String,
+    pub requires_python: Option<String>,
+    pub is_yanked: bool,
+}
+
+/// Package registry from which release metadata is fetched.
+pub trait Registry {
+    /// Returns the release versions of the package and the distribution files of each.
+    fn get_releases(&self, package_name: &str) -> Result<BTreeMap<String, Vec<ReleaseFile>>>;
+
+    /// Returns the `requires_dist` requirement strings of the package release.
+    ///
+    /// Fails if the release has no dependency metadata, as its dependencies are unknown.
+    fn get_requires_dist(&self, package_name: &str, package_version: &str) -> Result<Vec<String>>;
+}
+
+/// Resolved package, its pinned version and the names of its dependencies.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub version: String,
+    pub extras: BTreeSet<String>,
+    pub dependencies: BTreeSet<String>,
+}
+
+/// Complete pinned dependency tree of a package.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Resolution {
+    pub name: String,
+    pub python_version: String,
+    pub platform: String,
+    pub packages: BTreeMap<String, ResolvedPackage>,
+    // Dependency cycles, each starting and ending with the same package name.
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl Resolution {
+    pub fn root(&self) -> Option<&ResolvedPackage> {
+        self.packages.get(&self.name)
+    }
+}
+
+/// Requirement and the package which declares it. Root requirements have no parent.
+#[derive(Debug, Clone)]
+struct PendingRequirement {
+    parent: Option<String>,
+    requirement: requirements::Requirement,
+}
+
+/// Partial resolution.
+#[derive(Debug, Clone, Default)]
+struct State {
+    packages: BTreeMap<String, ResolvedPackage>,
+    pending: VecDeque<PendingRequirement>,
+    // Version specifiers applied to each package and the package which applies each.
+    constraints: BTreeMap<String, Vec<(String, String)>>,
+}
+
+/// Returns true if the requirement applies within the target environment given the requested
+/// extras.
+///
+/// Requirements conditional on an extra (e.g. `extra == "socks"`) only apply if the extra is
+/// requested.
+fn applies(
+    requirement: &requirements::Requirement,
+    environment: &environment::Environment,
+    extras: &BTreeSet<String>,
+) -> Result<bool> {
+    match &requirement.marker {
+        Some(marker) => marker::evaluate(&marker, &environment, &extras),
+        None => Ok(true),
+    }
+}
+
+/// Returns true if the wheel file name tags are compatible with the target environment.
+///
+/// Wheel file names have the form `{name}-{version}(-{build})?-{python}-{abi}-{platform}.whl`,
+/// where each tag may be a `.` separated set.
+fn is_compatible_wheel(filename: &str, environment: &environment::Environment) -> bool {
+    let components: Vec<&str> = filename.trim_end_matches(".whl").split('-').collect();
+    if components.len() < 5 {
+        return false;
+    }
+    let tags = &components[components.len() - 3..];
+    let is_compatible_python = tags[0].split('.').any(|python_tag| {
+        tags[1]
+            .split('.')
+            .any(|abi_tag| environment.is_compatible_python_tag(&python_tag, &abi_tag))
+    });
+    is_compatible_python
+        && tags[2]
+            .split('.')
+            .any(|platform_tag| environment.is_compatible_platform_tag(&platform_tag))
+}
+
+/// Returns true if the distribution file can be installed within the target environment.
+fn is_compatible_file(file: &ReleaseFile, environment: &environment::Environment) -> bool {
+    if file.is_yanked {
+        return false;
+    }
+    // Invalid `requires_python` values are ignored.
+    let requires_python = file
+        .requires_python
+        .as_ref()
+        .and_then(|v| pep440::SpecifierSet::parse(&v).ok());
+    if let Some(requires_python) = requires_python {
+        if !requires_python.contains(&environment.python_version, true) {
+            return false;
+        }
+    }
+    match file.package_type.as_str() {
+        "sdist" => true,
+        "bdist_wheel" => is_compatible_wheel(&file.filename, &environment),
+        _ => false,
+    }
+}
+
+/// Returns cycles within the dependency graph reachable from the root package.
+fn find_cycles(root: &str, packages: &BTreeMap<String, ResolvedPackage>) -> Vec<Vec<String>> {
+    fn visit(
+        name: &str,
+        packages: &BTreeMap<String, ResolvedPackage>,
+        stack: &mut Vec<String>,
+        visited: &mut BTreeSet<String>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(index) = stack.iter().position(|v| v == name) {
+            let mut cycle = stack[index..].to_vec();
+            cycle.push(name.to_string());
+            cycles.push(cycle);
+            return;
+        }
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        stack.push(name.to_string());
+        if let Some(package) = packages.get(name) {
+            for dependency in &package.dependencies {
+                visit(&dependency, &packages, stack, visited, cycles);
+            }
+        }
+        stack.pop();
+    }
+
+    let mut cycles = Vec::new();
+    visit(
+        &root,
+        &packages,
+        &mut Vec::new(),
+        &mut BTreeSet::new(),
+        &mut cycles,
+    );
+    cycles
+}
+
+/// Backtracking dependency resolver.
+///
+/// Requirements are processed breadth first. When a package is first required, the newest
+/// compatible version which satisfies the requirement is selected. If a later requirement
+/// conflicts with a selected version, the most recent selection is revisited.
+struct Resolver<'a> {
+    registry: &'a dyn Registry,
+    environment: &'a environment::Environment,
+    // Compatible release versions of each package, newest first.
+    releases: HashMap<String, Vec<(pep440::Version, String)>>,
+    requirements: HashMap<(String, String), Vec<requirements::Requirement>>,
+    attempts: usize,
+    conflict: Option<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn get_releases(&mut self, package_name: &str) -> Result<&Vec<(pep440::Version, String)>> {
+        if !self.releases.contains_key(package_name) {
+            let mut releases = Vec::new();
+            for (version_string, files) in self.registry.get_releases(&package_name)? {
+                // Releases with non PEP 440 versions can not be compared and are skipped.
+                let version = match pep440::Version::parse(&version_string) {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+                if files
+                    .iter()
+                    .any(|file| is_compatible_file(&file, &self.environment))
+                {
+                    releases.push((version, version_string));
+                }
+            }
+            releases.sort_by(|(a, _), (b, _)| b.cmp(a));
+            self.releases.insert(package_name.to_string(), releases);
+        }
+        Ok(&self.releases[package_name])
+    }
+
+    fn get_requirements(
+        &mut self,
+        package_name: &str,
+        package_version: &str,
+    ) -> Result<&Vec<requirements::Requirement>> {
+        let key = (package_name.to_string(), package_version.to_string());
+        if !self.requirements.contains_key(&key) {
+            let mut package_requirements = Vec::new();
+            for entry in self
+                .registry
+                .get_requires_dist(&package_name, &package_version)?
+            {
+                package_requirements.push(requirements::parse_requirement(&entry).context(
+                    format!(
+                        "Failed to parse 'requires_dist' entry '{}' of package: {} {}",
+                        entry, package_name, package_version
+                    ),
+                )?);
+            }
+            self.requirements.insert(key.clone(), package_requirements);
+        }
+        Ok(&self.requirements[&key])
+    }
+
+    /// Queue requirements of the package which apply given the newly requested extras.
+    fn queue_requirements(
+        &mut self,
+        state: &mut State,
+        package_name: &str,
+        previous_extras: Option<&BTreeSet<String>>,
+    ) -> Result<()> {
+        let package = &state.packages[package_name];
+        let (version, extras) = (package.version.clone(), package.extras.clone());
+        let environment = self.environment;
+        for requirement in self.get_requirements(&package_name, &version)? {
+            let is_queued = match previous_extras {
+                Some(previous_extras) => applies(&requirement, &environment, &previous_extras)
+                    .context(format!(
+                        "Failed to evaluate requirement of package: {} {}",
+                        package_name, version
+                    ))?,
+                None => false,
+            };
+            let is_applicable = applies(&requirement, &environment, &extras).context(format!(
+                "Failed to evaluate requirement of package: {} {}",
+                package_name, version
+            ))?;
+            if is_applicable && !is_queued {
+                state.pending.push_back(PendingRequirement {
+                    parent: Some(package_name.to_string()),
+                    requirement: requirement.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn record_conflict(&mut self, state: &State, package_name: &str) {
+        let constraints: Vec<String> = state.constraints[package_name]
+            .iter()
+            .map(|(specifier, required_by)| format!("'{}' required by {}", specifier, required_by))
+            .collect();
+        self.conflict = Some(format!(
+            "No compatible version of '{}' satisfies all requirements: {}",
+            package_name,
+            constraints.join(", ")
+        ));
+    }
+
+    /// Process pending requirements. Returns None if the partial resolution can not be
+    /// completed.
+    fn search(&mut self, mut state: State) -> Result<Option<State>> {
+        while let Some(pending) = state.pending.pop_front() {
+            let requirement = &pending.requirement;
+            let name = requirement.name.clone();
+            if let Some(url) = &requirement.url {
+                return Err(format_err!(
+                    "Failed to resolve direct URL requirement of '{}': {}",
+                    name,
+                    url
+                ));
+            }
+            let specifiers = pep440::SpecifierSet::parse(&requirement.specifier).context(
+                format!("Failed to parse version specifier of requirement: {}", name),
+            )?;
+
+            let required_by = match &pending.parent {
+                Some(parent) => {
+                    let parent = state.packages.get_mut(parent).ok_or(format_err!(
+                        "Code error: parent package not resolved: {}",
+                        parent
+                    ))?;
+                    parent.dependencies.insert(name.clone());
+                    format!("{} {}", parent.name, parent.version)
+                }
+                None => "the requested package".to_string(),
+            };
+            state
+                .constraints
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .push((requirement.specifier.clone(), required_by));
+
+            if let Some(package) = state.packages.get_mut(&name) {
+                let version = pep440::Version::parse(&package.version)?;
+                if !specifiers.contains(&version, true) {
+                    self.record_conflict(&state, &name);
+                    return Ok(None);
+                }
+                let previous_extras = package.extras.clone();
+                package.extras.extend(requirement.extras.iter().cloned());
+                if package.extras != previous_extras {
+                    self.queue_requirements(&mut state, &name, Some(&previous_extras))?;
+                }
+                continue;
+            }
+
+            let releases = self.get_releases(&name)?;
+            let mut candidates: Vec<String> = releases
+                .iter()
+                .filter(|(version, _)| specifiers.contains(&version, false))
+                .map(|(_, version_string)| version_string.clone())
+                .collect();
+            // Pre-releases are only selected if no final release is suitable.
+            if candidates.is_empty() {
+                candidates = releases
+                    .iter()
+                    .filter(|(version, _)| specifiers.contains(&version, true))
+                    .map(|(_, version_string)| version_string.clone())
+                    .collect();
+            }
+            if candidates.is_empty() {
+                self.record_conflict(&state, &name);
+                return Ok(None);
+            }
+
+            for candidate in candidates {
+                self.attempts += 1;
+                if self.attempts > MAX_ATTEMPTS {
+                    return Err(format_err!(
+                        "Failed to resolve dependencies within {} attempts.",
+                        MAX_ATTEMPTS
+                    ));
+                }
+                let mut next_state = state.clone();
+                next_state.packages.insert(
+                    name.clone(),
+                    ResolvedPackage {
+                        name: name.clone(),
+                        version: candidate,
+                        extras: requirement.extras.iter().cloned().collect(),
+                        dependencies: BTreeSet::new(),
+                    },
+                );
+                self.queue_requirements(&mut next_state, &name, None)?;
+                if let Some(resolved_state) = self.search(next_state)? {
+                    return Ok(Some(resolved_state));
+                }
+            }
+            return Ok(None);
+        }
+        Ok(Some(state))
+    }
+}
+
+/// Resolve the complete pinned dependency tree of a package.
+///
+/// The newest compatible release is resolved if no version is given. The given extras of the
+/// package are requested. Releases are compatible
+/// if they provide a source distribution or a wheel for the target environment and their
+/// `requires_python` range includes the target Python version. Dependency cycles are reported
+/// within the resolution. Conflicting requirements are reported as errors.
+pub fn resolve(
+    registry: &dyn Registry,
+    package_name: &str,
+    package_version: &Option<&str>,
+    extras: &BTreeSet<String>,
+    environment: &environment::Environment,
+) -> Result<Resolution> {
+    let name = requirements::normalize_name(&package_name);
+    let mut state = State::default();
+    state.pending.push_back(PendingRequirement {
+        parent: None,
+        requirement: requirements::Requirement {
+            name: name.clone(),
+            extras: extras.iter().cloned().collect(),
+            specifier: package_version
+                .map(|version| format!("=={}", version))
+                .unwrap_or_default(),
+            ..Default::default()
+        },
+    });
+
+    let mut resolver = Resolver {
+        registry: registry,
+        environment: environment,
+        releases: HashMap::new(),
+        requirements: HashMap::new(),
+        attempts: 0,
+        conflict: None,
+    };
+    let state = match resolver.search(state)? {
+        Some(v) => v,
+        None => {
+            return Err(format_err!(
+                "Failed to resolve dependencies of package '{}': {}",
+                name,
+                resolver
+                    .conflict
+                    .unwrap_or("no compatible version found".to_string())
+            ))
+        }
+    };
+
+    Ok(Resolution {
+        cycles: find_cycles(&name, &state.packages),
+        name: name,
+        python_version: environment.python_version.to_string(),
+        platform: environment.platform.clone(),
+        packages: state.packages,
+    })
+}
+
+/// Write the resolved dependency tree to a JSON file.
+pub fn write_tree_file(file_path: &std::path::PathBuf, resolution: &Resolution) -> Result<()> {
+    let file = std::fs::File::create(&file_path).context(format!(
+        "Failed to create dependency tree file: {}",
+        file_path.display()
+    ))?;
+    serde_json::to_writer_pretty(file, &resolution)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory registry of package releases and their requirements.
+    #[derive(Default)]
+    struct MockRegistry {
+        releases: BTreeMap<String, BTreeMap<String, Vec<ReleaseFile>>>,
+        requires_dist: BTreeMap<(String, String), Vec<String>>,
+    }
+
+    impl MockRegistry {
+        fn add(&mut self, name: &str, version: &str, requires_dist: &[&str]) -> &mut Self {
+            let file = ReleaseFile {
+                filename: format!("{}-{}.tar.gz", name, version),
+                package_type: "sdist".to_string(),
+                requires_python: None,
+                is_yanked: false,
+            };
+            self.add_file(name, version, file, requires_dist)
+        }
+
+        fn add_file(
+            &mut self,
+            name: &str,
+            version: &str,
+            file: ReleaseFile,
+            requires_dist: &[&str],
+        ) -> &mut Self {
+            self.releases
+                .entry(name.to_string())
+                .or_default()
+                .insert(version.to_string(), vec![file]);
+            self.requires_dist.insert(
+                (name.to_string(), version.to_string()),
+                requires_dist.iter().map(|v| v.to_string()).collect(),
+            );
+            self
+        }
+    }
+
+    impl Registry for MockRegistry {
+        fn get_releases(&self, package_name: &str) -> Result<BTreeMap<String, Vec<ReleaseFile>>> {
+            self.releases
+                .get(package_name)
+                .cloned()
+                .ok_or(format_err!("Package not found: {}", package_name))
+        }
+
+        fn get_requires_dist(
+            &self,
+            package_name: &str,
+            package_version: &str,
+        ) -> Result<Vec<String>> {
+            Ok(
+                self.requires_dist[&(package_name.to_string(), package_version.to_string())]
+                    .clone(),
+            )
+        }
+    }
+
+    fn linux() -> environment::Environment {
+        environment::Environment::new("3.12", "linux", "x86_64", "cpython").unwrap()
+    }
+
+    fn versions(resolution: &Resolution) -> Vec<(String, String)> {
+        resolution
+            .packages
+            .values()
+            .map(|package| (package.name.clone(), package.version.clone()))
+            .collect()
+    }
+
+    fn pins(pins: &[(&str, &str)]) -> Vec<(String, String)> {
+        pins.iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn newest_compatible_version() {
+        let mut registry = MockRegistry::default();
+        registry
+            .add("app", "1.0", &["lib>=1.0,<2.0"])
+            .add("lib", "1.0", &[])
+            .add("lib", "1.5", &[])
+            .add("lib", "1.9b1", &[])
+            .add("lib", "2.0", &[]);
+        let resolution = resolve(&registry, "App", &None, &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(resolution.name, "app");
+        assert_eq!(
+            versions(&resolution),
+            pins(&[("app", "1.0"), ("lib", "1.5")])
+        );
+        assert!(resolution.root().unwrap().dependencies.contains("lib"));
+        assert!(resolution.cycles.is_empty());
+    }
+
+    #[test]
+    fn prerelease_selected_if_no_final_release() {
+        let mut registry = MockRegistry::default();
+        registry
+            .add("app", "1.0", &["lib>=1.5"])
+            .add("lib", "1.0", &[])
+            .add("lib", "2.0rc1", &[]);
+        let resolution = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(
+            versions(&resolution),
+            pins(&[("app", "1.0"), ("lib", "2.0rc1")])
+        );
+    }
+
+    #[test]
+    fn backtracking() {
+        let mut registry = MockRegistry::default();
+        registry
+            .add("app", "1.0", &["x", "y"])
+            .add("x", "1.0", &["z"])
+            .add("x", "2.0", &["z==2.0"])
+            .add("y", "1.0", &["z==1.0"])
+            .add("z", "1.0", &[])
+            .add("z", "2.0", &[]);
+        let resolution = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(
+            versions(&resolution),
+            pins(&[("app", "1.0"), ("x", "1.0"), ("y", "1.0"), ("z", "1.0")])
+        );
+    }
+
+    #[test]
+    fn conflict() {
+        let mut registry = MockRegistry::default();
+        registry
+            .add("app", "1.0", &["x", "y"])
+            .add("x", "1.0", &["z==2.0"])
+            .add("y", "1.0", &["z==1.0"])
+            .add("z", "1.0", &[])
+            .add("z", "2.0", &[]);
+        let error = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with("Failed to resolve dependencies of package 'app'"));
+        assert!(message.contains("No compatible version of 'z'"));
+    }
+
+    #[test]
+    fn requested_version() {
+        let mut registry = MockRegistry::default();
+        registry.add("app", "1.0", &[]).add("app", "2.0", &[]);
+        let resolution =
+            resolve(&registry, "app", &Some("1.0"), &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(resolution.root().unwrap().version, "1.0");
+        assert!(resolve(&registry, "app", &Some("3.0"), &BTreeSet::new(), &linux()).is_err());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut registry = MockRegistry::default();
+        registry.add("app", "1.0", &["e"]).add("e", "1.0", &["app"]);
+        let resolution = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(
+            resolution.cycles,
+            vec![vec!["app".to_string(), "e".to_string(), "app".to_string()]]
+        );
+    }
+
+    #[test]
+    fn extras_and_markers() {
+        let mut registry = MockRegistry::default();
+        registry
+            .add(
+                "app",
+                "1.0",
+                &[
+                    "a",
+                    "b; extra == \"x\"",
+                    "c; sys_platform == \"win32\"",
+                    "d[y]",
+                ],
+            )
+            .add("a", "1.0", &[])
+            .add("b", "1.0", &[])
+            .add("c", "1.0", &[])
+            .add("d", "1.0", &["e; extra == 'y'"])
+            .add("e", "1.0", &[]);
+
+        let resolution = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap();
+        let names: Vec<&String> = resolution.packages.keys().collect();
+        assert_eq!(names, vec!["a", "app", "d", "e"]);
+
+        let extras: BTreeSet<String> = vec!["x".to_string()].into_iter().collect();
+        let resolution = resolve(&registry, "app", &None, &extras, &linux()).unwrap();
+        let names: Vec<&String> = resolution.packages.keys().collect();
+        assert_eq!(names, vec!["a", "app", "b", "d", "e"]);
+        assert_eq!(resolution.root().unwrap().extras, extras);
+    }
+
+    #[test]
+    fn incompatible_releases_skipped() {
+        let file =
+            |filename: &str, package_type: &str, requires_python: Option<&str>| ReleaseFile {
+                filename: filename.to_string(),
+                package_type: package_type.to_string(),
+                requires_python: requires_python.map(|v| v.to_string()),
+                is_yanked: false,
+            };
+        let mut registry = MockRegistry::default();
+        registry
+            .add("app", "1.0", &["lib"])
+            .add("lib", "1.0", &[])
+            .add_file(
+                "lib",
+                "2.0",
+                file("lib-2.0-cp312-cp312-linux_x86_64.whl", "bdist_wheel", None),
+                &[],
+            )
+            .add_file(
+                "lib",
+                "3.0",
+                file("lib-3.0-cp312-cp312-win_amd64.whl", "bdist_wheel", None),
+                &[],
+            )
+            .add_file(
+                "lib",
+                "4.0",
+                file("lib-4.0.tar.gz", "sdist", Some("<3")),
+                &[],
+            )
+            .add_file(
+                "lib",
+                "5.0",
+                ReleaseFile {
+                    is_yanked: true,
+                    ..file("lib-5.0.tar.gz", "sdist", None)
+                },
+                &[],
+            );
+        let resolution = resolve(&registry, "app", &None, &BTreeSet::new(), &linux()).unwrap();
+        assert_eq!(
+            versions(&resolution),
+            pins(&[("app", "1.0"), ("lib", "2.0")])
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{environment, location, requirements, source};
+
+/// Returns the content of the inline script metadata block (PEP 723), if present.
+///
+/// Block lines are comments between `# /// script` and `# ///`. The comment prefix is removed
+/// from each content line.
+fn get_metadata_block(source: &str) -> Result<Option<String>> {
+    let mut block: Option<String> = None;
+    let mut current_block: Option<String> = None;
+    for line in source.lines() {
+        let line = line.trim_end();
+        match current_block.as_mut() {
+            None => {
+                if line == "# /// script" {
+                    if block.is_some() {
+                        return Err(format_err!("Found multiple script metadata blocks."));
+                    }
+                    current_block = Some(String::new());
+                }
+            }
+            Some(content) => {
+                if line == "# ///" {
+                    block = current_block.take();
+                } else if line == "#" {
+                    content.push('\n');
+                } else if let Some(v) = line.strip_prefix("# ") {
+                    content.push_str(v);
+                    content.push('\n');
+                } else {
+                    // Not a comment line, therefore the block was never closed.
+                    current_block = None;
+                }
+            }
+        }
+    }
+    Ok(block)
+}
+
+/// Returns true if the Python script contains an inline script metadata block.
+fn has_metadata_block(file_path: &std::path::PathBuf) -> bool {
+    std::fs::read_to_string(&file_path)
+        .map(|source| source.contains("# /// script"))
+        .unwrap_or(false)
+}
+
+/// Parse dependencies from inline script metadata of a standalone Python script.
+///
+/// Requirements which do not apply within the target environment are excluded.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let block = get_metadata_block(&content)
+        .context(format!(
+            "Failed to parse script metadata of file: {}",
+            file_path.display()
+        ))?
+        .unwrap_or_default();
+    let metadata: toml::Value = toml::from_str(&block).context(format!(
+        "Failed to parse script metadata TOML of file: {}",
+        file_path.display()
+    ))?;
+
+    let requirement_strings = match metadata.get("dependencies") {
+        Some(v) => v
+            .as_array()
+            .ok_or(format_err!(
+                "Failed to parse 'dependencies' array of script metadata: {}",
+                file_path.display()
+            ))?
+            .clone(),
+        None => Vec::new(),
+    };
+
+    let mut all_dependencies = BTreeMap::new();
+    all_dependencies.insert(
+        source::PYPI_HOST_NAME.to_string(),
+        location::Dependencies::new(),
+    );
+    for (index, requirement_string) in requirement_strings.iter().enumerate() {
+        let requirement_string = requirement_string.as_str().ok_or(format_err!(
+            "Failed to parse requirement string of script metadata: {}",
+            file_path.display()
+        ))?;
+        let requirement = requirements::parse_requirement(&requirement_string).context(format!(
+            "Failed to parse requirement '{}' in file: {}",
+            requirement_string,
+            file_path.display()
+        ))?;
+        if !requirements::applies(&requirement, &environment)? {
+            continue;
+        }
+        all_dependencies
+            .entry(requirements::get_host_name(
+                &requirement,
+                source::PYPI_HOST_NAME,
+            ))
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                requirements::to_dependency(&requirement),
+                location::Location::find(
+                    &file_path,
+                    &content,
+                    &["# /// script", "dependencies", requirement_string],
+                )
+                .with_key_path(&format!("dependencies[{}]", index)),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+/// Returns Python scripts with inline script metadata found within the given directory.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_python_file = path.extension().map_or(false, |e| e == "py");
+            if path.is_file() && is_python_file && has_metadata_block(&path) {
+                file_paths.push(path);
+            }
+        }
+    }
+    file_paths.sort();
+    file_paths
+}
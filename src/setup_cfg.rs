@@ -0,0 +1,163 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{environment, ini, location, requirements, source};
+
+/// Environment marker variable names.
+///
+/// Used to identify markers which follow a semicolon separator in single line values.
+static MARKER_VARIABLES: &[&str] = &[
+    "python_version",
+    "python_full_version",
+    "os_name",
+    "sys_platform",
+    "platform_",
+    "implementation_",
+    "extra",
+];
+
+/// Split requirements list value into requirement strings.
+///
+/// Multi-line values list one requirement per line. Single line values separate requirements with
+/// semicolons, as setuptools does, unless the semicolon introduces an environment marker.
+fn split_requirements_list(value: &str) -> Vec<String> {
+    let chunks: Vec<&str> = if value.contains('\n') {
+        value.lines().collect()
+    } else {
+        value.split(';').collect()
+    };
+
+    let mut requirement_strings: Vec<String> = Vec::new();
+    for chunk in chunks {
+        let chunk = chunk.trim();
+        if chunk.is_empty() || chunk.starts_with('#') {
+            continue;
+        }
+        let is_marker = MARKER_VARIABLES
+            .iter()
+            .any(|variable| chunk.starts_with(variable));
+        match requirement_strings.last_mut() {
+            Some(previous) if is_marker && !value.contains('\n') => {
+                previous.push_str("; ");
+                previous.push_str(chunk);
+            }
+            _ => requirement_strings.push(chunk.to_string()),
+        }
+    }
+    requirement_strings
+}
+
+/// Returns requirement strings from requirements list value and their locations.
+///
+/// Values starting with `file:` reference requirements files relative to the setup.cfg file.
+/// Locations within setup.cfg are found by searching for the section and key `patterns`.
+fn get_requirement_strings(
+    value: &str,
+    directory: &std::path::Path,
+    file_path: &std::path::PathBuf,
+    content: &str,
+    patterns: &[&str],
+) -> Result<Vec<(String, location::Location)>> {
+    let file_paths = match value.trim().strip_prefix("file:") {
+        Some(v) => v,
+        None => {
+            return Ok(split_requirements_list(&value)
+                .into_iter()
+                .map(|requirement_string| {
+                    let patterns: Vec<&str> = patterns
+                        .iter()
+                        .cloned()
+                        .chain(requirement_string.split(';').take(1))
+                        .collect();
+                    let location = location::Location::find(&file_path, &content, &patterns);
+                    (requirement_string, location)
+                })
+                .collect());
+        }
+    };
+
+    let mut requirement_strings = Vec::new();
+    for file_path in file_paths.split(',').map(|path| path.trim()) {
+        let file_path = directory.join(file_path);
+        let content = std::fs::read_to_string(&file_path).context(format!(
+            "Failed to read requirements file referenced from setup.cfg: {}",
+            file_path.display()
+        ))?;
+        requirement_strings.extend(requirements::get_logical_lines(&content).into_iter().map(
+            |(line_number, line)| (line, location::Location::from_line(&file_path, line_number)),
+        ));
+    }
+    Ok(requirement_strings)
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Includes `install_requires` and `setup_requires` from the `[options]` section and all groups
+/// of the `[options.extras_require]` section. Requirements which do not apply within the target
+/// environment are excluded.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let directory = file_path.parent().ok_or(format_err!(
+        "Failed to find parent directory of file: {}",
+        file_path.display()
+    ))?;
+
+    let mut requirement_strings = Vec::new();
+    for section in ini::parse(&content) {
+        let section_header = format!("[{}]", section.name);
+        if section.name == "options" {
+            for key in vec!["install_requires", "setup_requires"] {
+                if let Some(value) = section.get(key) {
+                    requirement_strings.extend(get_requirement_strings(
+                        &value,
+                        &directory,
+                        &file_path,
+                        &content,
+                        &[&section_header, key],
+                    )?);
+                }
+            }
+        } else if section.name == "options.extras_require" {
+            for (key, value) in &section.entries {
+                requirement_strings.extend(get_requirement_strings(
+                    &value,
+                    &directory,
+                    &file_path,
+                    &content,
+                    &[&section_header, key],
+                )?);
+            }
+        }
+    }
+
+    let mut all_dependencies = BTreeMap::new();
+    all_dependencies.insert(
+        source::PYPI_HOST_NAME.to_string(),
+        location::Dependencies::new(),
+    );
+    for (requirement_string, location) in requirement_strings {
+        let requirement =
+            requirements::parse_requirement_line(&requirement_string).context(format!(
+                "Failed to parse requirement '{}' in file: {}",
+                requirement_string,
+                file_path.display()
+            ))?;
+        let requirement = match requirement {
+            Some(v) => v,
+            None => continue,
+        };
+        if requirements::applies(&requirement, &environment)? {
+            all_dependencies
+                .entry(requirements::get_host_name(
+                    &requirement,
+                    source::PYPI_HOST_NAME,
+                ))
+                .or_insert_with(location::Dependencies::new)
+                .insert(requirements::to_dependency(&requirement), location);
+        }
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
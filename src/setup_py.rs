@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+use crate::tokenizer::{tokenize, Token};
+use crate::{environment, location, requirements, source, version_error};
+
+/// Parse literal list or tuple of strings starting at the opening bracket.
+///
+/// Returns None if any element is not a string literal or if the list is concatenated with
+/// another value.
+fn parse_literal_list(tokens: &Vec<Token>, start: usize) -> Option<Vec<String>> {
+    let closing_bracket = match tokens.get(start) {
+        Some(Token::Operator('[')) => ']',
+        Some(Token::Operator('(')) => ')',
+        _ => return None,
+    };
+
+    let mut values = Vec::new();
+    let mut current_value: Option<String> = None;
+    let mut index = start + 1;
+    loop {
+        match tokens.get(index)? {
+            // Adjacent string literals are concatenated.
+            Token::String(v) => {
+                current_value = Some(current_value.unwrap_or_default() + v);
+            }
+            Token::Operator(',') => {
+                values.push(current_value.take()?);
+            }
+            Token::Operator(c) if *c == closing_bracket => {
+                if let Some(v) = current_value.take() {
+                    values.push(v);
+                }
+                break;
+            }
+            _ => return None,
+        }
+        index += 1;
+    }
+
+    match tokens.get(index + 1) {
+        Some(Token::Operator('+')) | Some(Token::Operator('*')) => None,
+        _ => Some(values),
+    }
+}
+
+/// Returns token index following the assignment operator of the only assignment to the name.
+///
+/// Returns None if the name is not assigned exactly once or if it is modified after assignment.
+fn find_single_assignment(tokens: &Vec<Token>, name: &str) -> Option<usize> {
+    let mut assignment_index = None;
+    for (index, token) in tokens.iter().enumerate() {
+        if *token != Token::Name(name.to_string()) {
+            continue;
+        }
+        let previous_token = if index > 0 {
+            tokens.get(index - 1)
+        } else {
+            None
+        };
+        if previous_token == Some(&Token::Operator('.')) {
+            continue;
+        }
+        match (tokens.get(index + 1), tokens.get(index + 2)) {
+            // Modified in place, e.g. `name.append(...)` or `name += [...]`.
+            (Some(Token::Operator('.')), _) => return None,
+            (Some(Token::Operator(c)), Some(Token::Operator('='))) if "+-*|".contains(*c) => {
+                return None
+            }
+            (Some(Token::Operator('=')), Some(Token::Operator('='))) => {}
+            (Some(Token::Operator('=')), _) => {
+                // Keyword arguments are preceded by an opening bracket or comma.
+                let is_keyword_argument = match previous_token {
+                    Some(Token::Operator('(')) | Some(Token::Operator(',')) => true,
+                    _ => false,
+                };
+                if is_keyword_argument {
+                    continue;
+                }
+                if assignment_index.is_some() {
+                    return None;
+                }
+                assignment_index = Some(index + 2);
+            }
+            _ => {}
+        }
+    }
+    assignment_index
+}
+
+/// Returns true if a `setup(...)` call unpacks keyword arguments, e.g. `setup(**kwargs)`.
+fn has_unpacked_setup_arguments(tokens: &Vec<Token>) -> bool {
+    for (index, token) in tokens.iter().enumerate() {
+        let is_setup_call = *token == Token::Name("setup".to_string())
+            && tokens.get(index + 1) == Some(&Token::Operator('('))
+            && (index == 0 || tokens.get(index - 1) != Some(&Token::Name("def".to_string())));
+        if !is_setup_call {
+            continue;
+        }
+        let mut depth = 0;
+        for (argument_index, argument_token) in tokens.iter().enumerate().skip(index + 1) {
+            match argument_token {
+                Token::Operator('(') | Token::Operator('[') | Token::Operator('{') => depth += 1,
+                Token::Operator(')') | Token::Operator(']') | Token::Operator('}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Token::Operator('*') if depth == 1 => {
+                    let is_unpacking = tokens.get(argument_index + 1)
+                        == Some(&Token::Operator('*'))
+                        && (tokens.get(argument_index - 1) == Some(&Token::Operator('('))
+                            || tokens.get(argument_index - 1) == Some(&Token::Operator(',')));
+                    if is_unpacking {
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+/// Returns requirement strings given to `install_requires`.
+///
+/// Returns None if the requirements are computed dynamically. This includes `setup(...)` calls
+/// which unpack keyword arguments and `install_requires` given as a string key (e.g.
+/// `setup(**{"install_requires": reqs})`).
+fn get_install_requires(tokens: &Vec<Token>) -> Option<Vec<String>> {
+    if has_unpacked_setup_arguments(&tokens)
+        || tokens.contains(&Token::String("install_requires".to_string()))
+    {
+        return None;
+    }
+    let keyword = Token::Name("install_requires".to_string());
+    let mut all_requirement_strings = Vec::new();
+    for (index, _) in tokens.iter().enumerate().filter(|(_, t)| **t == keyword) {
+        let previous_token = if index > 0 {
+            tokens.get(index - 1)
+        } else {
+            None
+        };
+        let is_keyword_argument = (previous_token == Some(&Token::Operator('('))
+            || previous_token == Some(&Token::Operator(',')))
+            && tokens.get(index + 1) == Some(&Token::Operator('='))
+            && tokens.get(index + 2) != Some(&Token::Operator('='));
+        if !is_keyword_argument {
+            continue;
+        }
+
+        let requirement_strings = match tokens.get(index + 2)? {
+            Token::Name(name) => {
+                // A name which is assigned a literal list exactly once.
+                let assignment_index = find_single_assignment(&tokens, &name)?;
+                parse_literal_list(&tokens, assignment_index)?
+            }
+            _ => parse_literal_list(&tokens, index + 2)?,
+        };
+        all_requirement_strings.extend(requirement_strings);
+    }
+    Some(all_requirement_strings)
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// The file is never executed. Literal `install_requires` lists are extracted statically. If the
+/// list is computed dynamically a dependency named `install_requires` is returned with an error
+/// which notes that the dependencies can not be determined. Requirements which do not apply
+/// within the target environment are excluded.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let tokens = tokenize(&content);
+
+    let mut all_dependencies = BTreeMap::new();
+    all_dependencies.insert(
+        source::PYPI_HOST_NAME.to_string(),
+        location::Dependencies::new(),
+    );
+    let requirement_strings = match get_install_requires(&tokens) {
+        Some(v) => v,
+        None => {
+            all_dependencies
+                .entry(source::PYPI_HOST_NAME.to_string())
+                .or_insert_with(location::Dependencies::new)
+                .insert(
+                    vouch_lib::extension::Dependency {
+                        name: "install_requires".to_string(),
+                        version: Err(
+                            vouch_lib::extension::common::VersionError::from_parse_error(
+                                version_error::DYNAMIC_DEPENDENCIES_ERROR,
+                            ),
+                        ),
+                    },
+                    location::Location::find(&file_path, &content, &["install_requires"]),
+                );
+            Vec::new()
+        }
+    };
+
+    for requirement_string in requirement_strings {
+        let requirement =
+            requirements::parse_requirement_line(&requirement_string).context(format!(
+                "Failed to parse requirement '{}' in file: {}",
+                requirement_string,
+                file_path.display()
+            ))?;
+        let requirement = match requirement {
+            Some(v) => v,
+            None => continue,
+        };
+        if requirements::applies(&requirement, &environment)? {
+            all_dependencies
+                .entry(requirements::get_host_name(
+                    &requirement,
+                    source::PYPI_HOST_NAME,
+                ))
+                .or_insert_with(location::Dependencies::new)
+                .insert(
+                    requirements::to_dependency(&requirement),
+                    location::Location::find(&file_path, &content, &[&requirement_string]),
+                );
+        }
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_install_requires() {
+        let tokens = tokenize("setup(name='a', install_requires=['requests>=2', 'idna'])");
+        assert_eq!(
+            get_install_requires(&tokens),
+            Some(vec!["requests>=2".to_string(), "idna".to_string()])
+        );
+
+        let tokens = tokenize("def setup(**kwargs):\n    pass\nsetup(install_requires=[])");
+        assert_eq!(get_install_requires(&tokens), Some(vec![]));
+    }
+
+    #[test]
+    fn dynamic_install_requires() {
+        assert_eq!(get_install_requires(&tokenize("setup(**kwargs)")), None);
+        assert_eq!(
+            get_install_requires(&tokenize("setup(name='a', **{'install_requires': reqs})")),
+            None
+        );
+        assert_eq!(
+            get_install_requires(&tokenize(
+                "kwargs = {'install_requires': reqs}\nsetup(**kwargs)"
+            )),
+            None
+        );
+        assert_eq!(
+            get_install_requires(&tokenize("setup(install_requires=reqs + ['idna'])")),
+            None
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+use crate::{location, requirements, source};
+
+/// Returns value of header field from core metadata file content.
+///
+/// Only the header section preceding the description body is searched.
+fn get_metadata_field(content: &str, field: &str) -> Option<String> {
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some(index) = line.find(':') {
+            if line[..index].trim().eq_ignore_ascii_case(field) {
+                return Some(line[index + 1..].trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Returns registry host name and version for installed distribution.
+///
+/// Uses `direct_url.json` (PEP 610) where present. Editable and local installs are attributed to
+/// the local host name. VCS installs are pinned by their commit.
+fn get_host_name_and_version(
+    dist_info_path: &std::path::PathBuf,
+    version: &str,
+) -> Result<(String, vouch_lib::extension::common::VersionParseResult)> {
+    let direct_url_path = dist_info_path.join("direct_url.json");
+    if !direct_url_path.is_file() {
+        return Ok((source::PYPI_HOST_NAME.to_string(), Ok(version.to_string())));
+    }
+
+    let file = std::fs::File::open(&direct_url_path)?;
+    let reader = std::io::BufReader::new(file);
+    let direct_url: serde_json::Value = serde_json::from_reader(reader).context(format!(
+        "Failed to parse direct_url.json: {}",
+        direct_url_path.display()
+    ))?;
+    let url = direct_url["url"].as_str().ok_or(format_err!(
+        "Failed to parse URL of file: {}",
+        direct_url_path.display()
+    ))?;
+
+    let is_editable = direct_url["dir_info"]["editable"]
+        .as_bool()
+        .unwrap_or(false);
+    if is_editable || url.starts_with("file:") {
+        return Ok((source::LOCAL_HOST_NAME.to_string(), Ok(version.to_string())));
+    }
+    if let Some(commit_id) = direct_url["vcs_info"]["commit_id"].as_str() {
+        return Ok((source::get_host_name(&url)?, Ok(commit_id.to_string())));
+    }
+    Ok((source::get_host_name(&url)?, Ok(version.to_string())))
+}
+
+/// Parse installed distributions from site-packages directory.
+///
+/// Reads the name and version of each `*.dist-info/METADATA` file. Returns one file defined
+/// dependencies structure per registry host.
+pub fn get_file_defined_dependencies(
+    site_packages_path: &std::path::PathBuf,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let mut dist_info_paths: Vec<std::path::PathBuf> = std::fs::read_dir(&site_packages_path)
+        .context(format!(
+            "Failed to read site-packages directory: {}",
+            site_packages_path.display()
+        ))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path.extension().map_or(false, |e| e == "dist-info"))
+        .collect();
+    dist_info_paths.sort();
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for dist_info_path in dist_info_paths {
+        let metadata_path = dist_info_path.join("METADATA");
+        let content = std::fs::read_to_string(&metadata_path).context(format!(
+            "Failed to read distribution metadata: {}",
+            metadata_path.display()
+        ))?;
+        let name = get_metadata_field(&content, "Name").ok_or(format_err!(
+            "Failed to parse 'Name' field of file: {}",
+            metadata_path.display()
+        ))?;
+        let version = get_metadata_field(&content, "Version").ok_or(format_err!(
+            "Failed to parse 'Version' field of file: {}",
+            metadata_path.display()
+        ))?;
+        let (host_name, version) = get_host_name_and_version(&dist_info_path, &version)?;
+
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: requirements::normalize_name(&name),
+                    version,
+                },
+                location::Location::find(&metadata_path, &content, &["Name:"]),
+            );
+    }
+    Ok(location::from_registry_hosts(
+        &site_packages_path,
+        all_dependencies,
+    ))
+}
+
+/// Returns site-packages directories for the given directory.
+///
+/// If the directory is a virtual environment (contains `pyvenv.cfg`), returns its site-packages
+/// directories. If the directory is itself a site-packages directory, returns the directory.
+pub fn find_directories(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let directory_name = directory.file_name().and_then(|name| name.to_str());
+    if directory_name == Some("site-packages") || directory_name == Some("dist-packages") {
+        return vec![directory.clone()];
+    }
+    if !directory.join("pyvenv.cfg").is_file() {
+        return Vec::new();
+    }
+
+    // Windows virtual environments use `Lib/site-packages`. POSIX virtual environments use
+    // `lib/pythonX.Y/site-packages`, where `lib64` may link to `lib`.
+    let mut candidate_paths = vec![directory.join("Lib").join("site-packages")];
+    for lib_directory_name in vec!["lib", "lib64"] {
+        if let Ok(entries) = std::fs::read_dir(directory.join(lib_directory_name)) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                candidate_paths.push(entry.path().join("site-packages"));
+            }
+        }
+    }
+
+    let mut directories = Vec::new();
+    for path in candidate_paths {
+        if let Ok(path) = std::fs::canonicalize(&path) {
+            if path.is_dir() && !directories.contains(&path) {
+                directories.push(path);
+            }
+        }
+    }
+    directories.sort();
+    directories
+}
@@ -0,0 +1,47 @@
+use anyhow::{format_err, Result};
+
+/// Registry host name of the Python Package Index.
+pub static PYPI_HOST_NAME: &str = "pypi.org";
+
+/// Registry host name used for packages which are sourced from the local filesystem.
+///
+/// These packages can not be reviewed from a registry.
+pub static LOCAL_HOST_NAME: &str = "localhost";
+
+/// Returns host name from source URL.
+///
+/// Supports VCS prefixed URLs such as `git+https://github.com/owner/repo` and scp-like git URLs
+/// such as `git@github.com:owner/repo.git`.
+pub fn get_host_name(url: &str) -> Result<String> {
+    let url = url.trim();
+    let stripped_url = match url.find('+') {
+        Some(index) if url[..index].chars().all(|c| c.is_ascii_alphabetic()) => &url[index + 1..],
+        _ => url,
+    };
+
+    if !stripped_url.contains("://") {
+        if let (Some(at_index), Some(colon_index)) =
+            (stripped_url.find('@'), stripped_url.find(':'))
+        {
+            if at_index < colon_index {
+                return Ok(stripped_url[at_index + 1..colon_index].to_string());
+            }
+        }
+    }
+
+    url::Url::parse(&stripped_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .ok_or(format_err!("Failed to parse host name from URL: {}", url))
+}
+
+/// Returns registry host name from package index URL.
+///
+/// Host names which serve PyPI distribution files are mapped to the PyPI registry host name.
+pub fn get_index_host_name(index_url: &str) -> Result<String> {
+    let host_name = get_host_name(&index_url)?;
+    Ok(match host_name.as_str() {
+        "files.pythonhosted.org" | "pypi.python.org" | "www.pypi.org" => PYPI_HOST_NAME.to_string(),
+        _ => host_name,
+    })
+}
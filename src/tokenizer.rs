@@ -0,0 +1,92 @@
+/// Python source token.
+///
+/// Only the tokens required to identify literal lists of strings are distinguished.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    String(String),
+    // Formatted string literals are computed at runtime.
+    FormattedString,
+    Name(String),
+    Operator(char),
+}
+
+/// Tokenize Python source code. Comments and whitespace are dropped.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_whitespace() || c == '\\' {
+            index += 1;
+        } else if c == '#' {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            let name: String = chars[start..index].iter().collect();
+
+            // Names directly followed by a quote are string prefixes.
+            let is_string_prefix = index < chars.len()
+                && (chars[index] == '"' || chars[index] == '\'')
+                && name.len() <= 2
+                && name.chars().all(|c| "rRbBuUfF".contains(c));
+            if !is_string_prefix {
+                tokens.push(Token::Name(name));
+                continue;
+            }
+            let (value, end) = read_string(&chars, index, name.contains(|c| c == 'r' || c == 'R'));
+            index = end;
+            if name.contains(|c| c == 'f' || c == 'F') {
+                tokens.push(Token::FormattedString);
+            } else {
+                tokens.push(Token::String(value));
+            }
+        } else if c == '"' || c == '\'' {
+            let (value, end) = read_string(&chars, index, false);
+            index = end;
+            tokens.push(Token::String(value));
+        } else {
+            tokens.push(Token::Operator(c));
+            index += 1;
+        }
+    }
+    tokens
+}
+
+/// Read string literal starting at the opening quote.
+///
+/// Returns string value and the index following the closing quote.
+fn read_string(chars: &Vec<char>, start: usize, is_raw: bool) -> (String, usize) {
+    let quote = chars[start];
+    let is_triple_quoted =
+        chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let quote_length = if is_triple_quoted { 3 } else { 1 };
+
+    let mut value = String::new();
+    let mut index = start + quote_length;
+    while index < chars.len() {
+        let c = chars[index];
+        if c == '\\' && index + 1 < chars.len() {
+            if is_raw {
+                value.push(c);
+            }
+            value.push(chars[index + 1]);
+            index += 2;
+            continue;
+        }
+        let is_closing_quote = c == quote
+            && (!is_triple_quoted
+                || (chars.get(index + 1) == Some(&quote) && chars.get(index + 2) == Some(&quote)));
+        if is_closing_quote {
+            return (value, index + quote_length);
+        }
+        value.push(c);
+        index += 1;
+    }
+    (value, index)
+}
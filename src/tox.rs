@@ -0,0 +1,359 @@
+use crate::{environment, ini, location, pip, requirements, source, version_error};
+use anyhow::{format_err, Context, Result};
+use std::collections::BTreeMap;
+
+static MAX_REFERENCE_DEPTH: usize = 8;
+
+/// Test environment and its `deps` entries.
+///
+/// Entries which can not be determined statically are None.
+#[derive(Debug, Clone)]
+struct Environment {
+    name: String,
+    deps: Vec<Option<String>>,
+    // Patterns which locate the `deps` declaration within the file.
+    deps_patterns: Vec<String>,
+    // Key path of the `deps` array within TOML configuration.
+    deps_key_path: Option<String>,
+}
+
+/// Expand `{[section]key}` references to values of other sections.
+fn expand_references(value: &str, sections: &Vec<ini::Section>, depth: usize) -> String {
+    let mut result = String::new();
+    let mut remainder = value;
+    while let Some(start) = remainder.find("{[") {
+        result.push_str(&remainder[..start]);
+        let reference = &remainder[start..];
+        let end = match reference.find('}') {
+            Some(v) => v,
+            None => break,
+        };
+        let referenced_value = reference[2..end]
+            .split_once(']')
+            .and_then(|(section_name, key)| {
+                sections
+                    .iter()
+                    .find(|section| section.name == section_name)
+                    .and_then(|section| section.get(key))
+            });
+        match referenced_value {
+            Some(v) if depth < MAX_REFERENCE_DEPTH => {
+                result.push_str(&expand_references(v, sections, depth + 1))
+            }
+            _ => result.push_str(&reference[..=end]),
+        }
+        remainder = &reference[end + 1..];
+    }
+    result.push_str(remainder);
+    result
+}
+
+/// Remove factor condition prefix (e.g. `py38,py39: ` or `!lint: `) from deps line.
+fn strip_factor_condition(line: &str) -> &str {
+    let index = match line.find(':') {
+        Some(v) => v,
+        None => return line,
+    };
+    let is_factor_condition = index > 0
+        && line[..index]
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-!{},.".contains(c))
+        && line[index + 1..].starts_with(char::is_whitespace);
+    if is_factor_condition {
+        line[index + 1..].trim_start()
+    } else {
+        line
+    }
+}
+
+/// Returns environments defined in tox INI configuration.
+///
+/// Environments which do not set `deps` inherit the `[testenv]` value.
+fn get_ini_environments(content: &str) -> Vec<Environment> {
+    let sections = ini::parse(&content);
+    let base_deps = sections
+        .iter()
+        .find(|section| section.name == "testenv")
+        .and_then(|section| section.get("deps"))
+        .map(|deps| ("testenv", deps));
+
+    let mut environments = Vec::new();
+    for section in &sections {
+        if section.name != "testenv" && !section.name.starts_with("testenv:") {
+            continue;
+        }
+        let deps = section
+            .get("deps")
+            .map(|deps| (section.name.as_str(), deps))
+            .or(base_deps);
+        let (deps_section_name, deps) = match deps {
+            Some((section_name, v)) => (section_name, expand_references(v, &sections, 0)),
+            None => continue,
+        };
+        environments.push(Environment {
+            name: section.name.clone(),
+            deps: deps
+                .lines()
+                .map(|line| strip_factor_condition(line.trim()))
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| Some(line.to_string()))
+                .collect(),
+            deps_patterns: vec![format!("[{}]", deps_section_name), "deps".to_string()],
+            deps_key_path: None,
+        });
+    }
+    environments
+}
+
+/// Returns `deps` entries of a native TOML environment table.
+fn get_toml_deps(deps: &toml::Value) -> Result<Vec<Option<String>>> {
+    let deps = deps
+        .as_array()
+        .ok_or(format_err!("Failed to parse environment 'deps' array."))?;
+    Ok(deps
+        .iter()
+        .map(|entry| entry.as_str().map(|v| v.to_string()))
+        .collect())
+}
+
+/// Returns environments defined in the `[tool.tox]` table of pyproject.toml.
+///
+/// Supports both the `legacy_tox_ini` string and native `env_run_base` and `env` tables.
+fn get_toml_environments(tox: &toml::Value) -> Result<Vec<Environment>> {
+    if let Some(legacy_tox_ini) = tox.get("legacy_tox_ini") {
+        let content = legacy_tox_ini
+            .as_str()
+            .ok_or(format_err!("Failed to parse 'legacy_tox_ini' string."))?;
+        return Ok(get_ini_environments(&content));
+    }
+
+    let mut environments = Vec::new();
+    let base_deps = tox
+        .get("env_run_base")
+        .and_then(|base| base.get("deps"))
+        .map(|deps| (vec!["env_run_base".to_string()], deps));
+    if let Some((deps_patterns, deps)) = &base_deps {
+        environments.push(Environment {
+            name: "testenv".to_string(),
+            deps: get_toml_deps(&deps)?,
+            deps_patterns: deps_patterns.clone(),
+            deps_key_path: Some("tool.tox.env_run_base.deps".to_string()),
+        });
+    }
+    if let Some(environment_tables) = tox.get("env").and_then(|env| env.as_table()) {
+        for (name, environment_table) in environment_tables {
+            let deps = environment_table
+                .get("deps")
+                .map(|deps| (vec!["env".to_string(), name.clone()], deps))
+                .or(base_deps.clone());
+            if let Some((deps_patterns, deps)) = deps {
+                let deps_key_path = format!("tool.tox.{}.deps", deps_patterns.join("."));
+                environments.push(Environment {
+                    name: format!("testenv:{}", name),
+                    deps: get_toml_deps(&deps)?,
+                    deps_patterns: deps_patterns,
+                    deps_key_path: Some(deps_key_path),
+                });
+            }
+        }
+    }
+    Ok(environments)
+}
+
+/// Convert deps entry into a dependency and its registry host name.
+///
+/// Entries which contain substitutions (e.g. `django=={env:VERSION}`) can not be resolved
+/// statically and are reported with a version error. Returns None for requirements which do not
+/// apply within the target environment.
+fn get_dependency(
+    entry: &str,
+    environment: &environment::Environment,
+) -> Result<Option<(String, vouch_lib::extension::Dependency)>> {
+    if !entry.contains('{') {
+        let requirement = match requirements::parse_requirement_line(&entry)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        if !requirements::applies(&requirement, &environment)? {
+            return Ok(None);
+        }
+        return Ok(Some((
+            requirements::get_host_name(&requirement, source::PYPI_HOST_NAME),
+            requirements::to_dependency(&requirement),
+        )));
+    }
+
+    let name: String = entry
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || "-_.".contains(*c))
+        .collect();
+    let (name, specifier) = if name.is_empty() {
+        (entry.to_string(), entry)
+    } else {
+        (
+            requirements::normalize_name(&name),
+            entry[name.len()..].trim(),
+        )
+    };
+    Ok(Some((
+        source::PYPI_HOST_NAME.to_string(),
+        vouch_lib::extension::Dependency {
+            name: name,
+            version: Err(vouch_lib::extension::common::VersionError::from_parse_error(specifier)),
+        },
+    )))
+}
+
+/// Returns the location of a `deps` entry.
+///
+/// Entries which are not found after the environment's `deps` declaration (e.g. entries included
+/// with a `{[section]key}` reference) are searched for within the whole file.
+fn get_location(
+    file_path: &std::path::PathBuf,
+    content: &str,
+    environment: &Environment,
+    index: usize,
+    entry: &str,
+) -> location::Location {
+    let mut patterns: Vec<&str> = environment
+        .deps_patterns
+        .iter()
+        .map(|v| v.as_str())
+        .collect();
+    patterns.push(entry);
+    let mut location = location::Location::find(&file_path, &content, &patterns);
+    if location.line.is_none() {
+        location = location::Location::find(&file_path, &content, &[entry]);
+    }
+    match &environment.deps_key_path {
+        Some(key_path) => location.with_key_path(&format!("{}[{}]", key_path, index)),
+        None => location,
+    }
+}
+
+/// Parse dependencies from tox test environment `deps` declarations.
+///
+/// Reads tox.ini or the `[tool.tox]` table of pyproject.toml. Returns one file defined
+/// dependencies structure per environment and registry host, labelled with the environment name
+/// (e.g. `tox.ini[testenv:lint]`). Requirements files installed with `-r` are relative to the
+/// configuration file.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    target_environment: &environment::Environment,
+    requirements_file_paths: &mut Vec<std::path::PathBuf>,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let environments = if file_path.file_name() == Some(std::ffi::OsStr::new("pyproject.toml")) {
+        let project: toml::Value = toml::from_str(&content)
+            .context(format!("Failed to parse file: {}", file_path.display()))?;
+        match project.get("tool").and_then(|tool| tool.get("tox")) {
+            Some(tox) => get_toml_environments(&tox).context(format!(
+                "Failed to parse tox table: {}",
+                file_path.display()
+            ))?,
+            None => Vec::new(),
+        }
+    } else {
+        get_ini_environments(&content)
+    };
+
+    let directory = file_path
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .to_path_buf();
+    let directory_string = directory.display().to_string();
+
+    let mut all_file_defined_dependencies = Vec::new();
+    for environment in &environments {
+        let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+        for (index, entry) in environment.deps.iter().enumerate() {
+            let (entry, location) = match entry {
+                Some(v) => (
+                    v.replace("{toxinidir}", &directory_string)
+                        .replace("{tox_root}", &directory_string),
+                    get_location(&file_path, &content, &environment, index, &v),
+                ),
+                None => {
+                    all_dependencies
+                        .entry(source::PYPI_HOST_NAME.to_string())
+                        .or_insert_with(location::Dependencies::new)
+                        .insert(
+                            vouch_lib::extension::Dependency {
+                                name: "deps".to_string(),
+                                version: Err(
+                                    vouch_lib::extension::common::VersionError::from_parse_error(
+                                        version_error::DYNAMIC_DEPENDENCIES_ERROR,
+                                    ),
+                                ),
+                            },
+                            get_location(&file_path, &content, &environment, index, "deps"),
+                        );
+                    continue;
+                }
+            };
+
+            // Option entries (e.g. `-r requirements.txt` or `-e .`) are pip install arguments.
+            let entries = if entry.starts_with('-') {
+                let arguments: Vec<String> =
+                    entry.split_whitespace().map(|v| v.to_string()).collect();
+                let install_command = pip::parse_install_arguments(&arguments);
+                for requirements_file in install_command.requirements_files {
+                    let requirements_file_path = directory.join(&requirements_file);
+                    if requirements_file_path.is_file() {
+                        requirements_file_paths.push(requirements_file_path);
+                    } else {
+                        all_dependencies
+                            .entry(source::PYPI_HOST_NAME.to_string())
+                            .or_insert_with(location::Dependencies::new)
+                            .insert(
+                                requirements::to_missing_file_dependency(&requirements_file),
+                                location.clone(),
+                            );
+                    }
+                }
+                install_command.requirement_strings
+            } else {
+                vec![entry]
+            };
+            for entry in entries {
+                let dependency = get_dependency(&entry, &target_environment).context(format!(
+                    "Failed to parse deps entry '{}' of environment '{}' in file: {}",
+                    entry,
+                    environment.name,
+                    file_path.display()
+                ))?;
+                if let Some((host_name, dependency)) = dependency {
+                    all_dependencies
+                        .entry(host_name)
+                        .or_insert_with(location::Dependencies::new)
+                        .insert(dependency, location.clone());
+                }
+            }
+        }
+
+        all_file_defined_dependencies.extend(location::from_registry_hosts(
+            &std::path::PathBuf::from(format!("{}[{}]", file_path.display(), environment.name)),
+            all_dependencies,
+        ));
+    }
+    Ok(all_file_defined_dependencies)
+}
+
+/// Returns tox configuration files found within the given directory.
+///
+/// Includes tox.ini and pyproject.toml files which contain a `[tool.tox]` table.
+pub fn find_files(directory: &std::path::PathBuf) -> Vec<std::path::PathBuf> {
+    let mut file_paths = Vec::new();
+    let tox_ini_path = directory.join("tox.ini");
+    if tox_ini_path.is_file() {
+        file_paths.push(tox_ini_path);
+    }
+    let pyproject_path = directory.join("pyproject.toml");
+    let has_tox_table = std::fs::read_to_string(&pyproject_path)
+        .map(|content| content.contains("[tool.tox"))
+        .unwrap_or(false);
+    if has_tox_table {
+        file_paths.push(pyproject_path);
+    }
+    file_paths
+}
@@ -0,0 +1,256 @@
+use anyhow::{format_err, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::{arguments, environment, location, marker, source};
+
+/// Returns registry host name and version for package.
+///
+/// Returns None for the virtual or editable root project.
+fn get_host_name_and_version(
+    package: &toml::Value,
+) -> Result<Option<(String, vouch_lib::extension::common::VersionParseResult)>> {
+    let version = match package.get("version").and_then(|v| v.as_str()) {
+        Some(v) => Ok(v.to_string()),
+        None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+    };
+    let source = package
+        .get("source")
+        .and_then(|v| v.as_table())
+        .ok_or(format_err!("Failed to parse package source table."))?;
+
+    if let Some(url) = source.get("registry").and_then(|v| v.as_str()) {
+        // Registries may also be local directories of distributions.
+        let host_name = match source::get_index_host_name(&url) {
+            Ok(v) => v,
+            Err(_) => source::LOCAL_HOST_NAME.to_string(),
+        };
+        return Ok(Some((host_name, version)));
+    }
+
+    if let Some(url) = source.get("git").and_then(|v| v.as_str()) {
+        // Git sources are pinned by the commit given in the URL fragment.
+        let commit = url::Url::parse(&url)
+            .ok()
+            .and_then(|url| url.fragment().map(|fragment| fragment.to_string()));
+        let version = match commit {
+            Some(v) => Ok(v),
+            None => Err(vouch_lib::extension::common::VersionError::from_missing_version()),
+        };
+        return Ok(Some((source::get_host_name(&url)?, version)));
+    }
+
+    if let Some(url) = source.get("url").and_then(|v| v.as_str()) {
+        return Ok(Some((source::get_host_name(&url)?, version)));
+    }
+
+    if source.contains_key("virtual") {
+        return Ok(None);
+    }
+    if let Some(path) = source.get("editable").and_then(|v| v.as_str()) {
+        if path == "." {
+            return Ok(None);
+        }
+    }
+    if source.contains_key("editable")
+        || source.contains_key("path")
+        || source.contains_key("directory")
+    {
+        return Ok(Some((source::LOCAL_HOST_NAME.to_string(), version)));
+    }
+
+    Err(format_err!(
+        "Unsupported package source: {}",
+        toml::Value::Table(source.clone())
+    ))
+}
+
+/// Returns names of the packages required by workspace members, excluding development
+/// dependencies.
+///
+/// Workspace members are the virtual and editable packages. Their `dependencies` and
+/// `optional-dependencies` are followed transitively, their `dev-dependencies` are not. Returns
+/// None if the lock file has no workspace members.
+fn get_production_package_names(packages: &Vec<toml::Value>) -> Option<HashSet<String>> {
+    let get_name = |package: &toml::Value| {
+        package
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+    let mut pending: Vec<String> = packages
+        .iter()
+        .filter(|package| {
+            package
+                .get("source")
+                .and_then(|v| v.as_table())
+                .map_or(false, |source| {
+                    source.contains_key("virtual") || source.contains_key("editable")
+                })
+        })
+        .filter_map(get_name)
+        .collect();
+    if pending.is_empty() {
+        return None;
+    }
+
+    let mut names = HashSet::new();
+    while let Some(name) = pending.pop() {
+        if !names.insert(name.clone()) {
+            continue;
+        }
+        for package in packages
+            .iter()
+            .filter(|package| get_name(package).as_ref() == Some(&name))
+        {
+            let mut dependency_lists = vec![package.get("dependencies")];
+            if let Some(optional_dependencies) = package
+                .get("optional-dependencies")
+                .and_then(|v| v.as_table())
+            {
+                dependency_lists.extend(optional_dependencies.values().map(Some));
+            }
+            for dependency in dependency_lists
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_array())
+                .flatten()
+            {
+                pending.extend(get_name(&dependency));
+            }
+        }
+    }
+    Some(names)
+}
+
+/// Parse dependencies from project dependencies definition file.
+///
+/// Returns one file defined dependencies structure per registry host. Git and local path sources are grouped
+/// separately from registry packages. Packages locked for other environments, where none of
+/// their `resolution-markers` hold within the target environment, are skipped. Packages which are
+/// only required as development dependencies are skipped if `production_only` is set.
+pub fn get_file_defined_dependencies(
+    file_path: &std::path::PathBuf,
+    arguments: &arguments::Arguments,
+    environment: &environment::Environment,
+) -> Result<Vec<location::FileDefinedDependencies>> {
+    let content = std::fs::read_to_string(&file_path)?;
+    let lock: toml::Value = toml::from_str(&content)
+        .context(format!("Failed to parse uv.lock: {}", file_path.display()))?;
+
+    let packages = match lock.get("package") {
+        Some(v) => v
+            .as_array()
+            .ok_or(format_err!("Failed to parse 'package' tables of uv.lock"))?
+            .clone(),
+        None => Vec::new(),
+    };
+
+    let production_package_names = if arguments.production_only {
+        get_production_package_names(&packages)
+    } else {
+        None
+    };
+
+    let mut all_dependencies: BTreeMap<String, location::Dependencies> = BTreeMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        let name = package
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or(format_err!("Failed to parse package name in uv.lock"))?;
+        if let Some(production_package_names) = &production_package_names {
+            if !production_package_names.contains(name) {
+                continue;
+            }
+        }
+        if let Some(resolution_markers) =
+            package.get("resolution-markers").and_then(|v| v.as_array())
+        {
+            let mut applies = resolution_markers.is_empty();
+            for resolution_marker in resolution_markers.iter().filter_map(|v| v.as_str()) {
+                if marker::evaluate(&resolution_marker, &environment, &BTreeSet::new())
+                    .context(format!("Failed to evaluate markers of package: {}", name))?
+                {
+                    applies = true;
+                    break;
+                }
+            }
+            if !applies {
+                continue;
+            }
+        }
+        let (host_name, version) = match get_host_name_and_version(&package)
+            .context(format!("Failed to parse source of package: {}", name))?
+        {
+            Some(v) => v,
+            None => continue,
+        };
+
+        all_dependencies
+            .entry(host_name)
+            .or_insert_with(location::Dependencies::new)
+            .insert(
+                vouch_lib::extension::Dependency {
+                    name: name.to_string(),
+                    version,
+                },
+                location::Location::find(&file_path, &content, &vec!["[[package]]"; index + 1])
+                    .with_key_path(&format!("package[{}]", index)),
+            );
+    }
+    Ok(location::from_registry_hosts(&file_path, all_dependencies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_host_names() {
+        let lock: toml::Value = toml::from_str(
+            r#"
+            [[package]]
+            name = "project"
+            version = "0.1.0"
+            source = { editable = "." }
+
+            [[package]]
+            name = "requests"
+            version = "2.31.0"
+            source = { registry = "https://pypi.org/simple" }
+
+            [[package]]
+            name = "internal"
+            version = "1.0"
+            source = { registry = "https://pypi.example.com/simple" }
+
+            [[package]]
+            name = "repo"
+            version = "1.0"
+            source = { git = "https://github.com/owner/repo.git?rev=main#abc123" }
+
+            [[package]]
+            name = "local"
+            version = "1.0"
+            source = { directory = "../local" }
+            "#,
+        )
+        .unwrap();
+        let packages: Vec<Option<(String, vouch_lib::extension::common::VersionParseResult)>> =
+            lock["package"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|package| get_host_name_and_version(&package).unwrap())
+                .collect();
+        assert_eq!(
+            packages,
+            vec![
+                None,
+                Some(("pypi.org".to_string(), Ok("2.31.0".to_string()))),
+                Some(("pypi.example.com".to_string(), Ok("1.0".to_string()))),
+                Some(("github.com".to_string(), Ok("abc123".to_string()))),
+                Some((source::LOCAL_HOST_NAME.to_string(), Ok("1.0".to_string()))),
+            ]
+        );
+    }
+}
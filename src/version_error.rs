@@ -0,0 +1,14 @@
+/// Dependencies which are computed at runtime and can not be determined statically.
+pub static DYNAMIC_DEPENDENCIES_ERROR: &str = "dynamic dependencies, cannot determine";
+
+/// Dependencies sourced from the local filesystem rather than a registry.
+pub static LOCAL_SOURCE_ERROR: &str = "local path dependency, not reviewable from a registry";
+
+/// Shell variables within install commands, which are only expanded at runtime.
+pub static SHELL_VARIABLE_ERROR: &str = "shell variable, cannot determine statically";
+
+/// Requirements files installed with `-r` which do not exist.
+pub static MISSING_REQUIREMENTS_FILE_ERROR: &str = "requirements file not found";
+
+/// Package releases published without dependency metadata, whose dependencies are unknown.
+pub static MISSING_METADATA_ERROR: &str = "no dependency metadata, dependencies unknown";
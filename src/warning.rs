@@ -0,0 +1,10 @@
+use crate::arguments;
+
+/// Print a warning to stderr, unless warnings are disabled by the `quiet` argument.
+///
+/// All warnings of the extension are reported through this function.
+pub fn print(message: &str, arguments: &arguments::Arguments) {
+    if !arguments.quiet {
+        eprintln!("Warning: {}", message);
+    }
+}